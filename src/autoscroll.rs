@@ -0,0 +1,58 @@
+//! scrolling the reader on a timer, by line or by paragraph
+
+use std::time::{Duration, Instant};
+
+use crate::config::AutoScrollConfig;
+
+// the speed keys stay between these
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+/// scrolls the reader while it's on
+pub struct AutoScroll {
+    /// time between two scrolls
+    pub interval: Duration,
+    /// scroll a whole paragraph at a time instead of one line
+    pub by_paragraph: bool,
+    /// stopped for now, the interval is kept
+    pub paused: bool,
+    last_scroll: Instant,
+}
+
+impl AutoScroll {
+    /// running from now, the interval is kept within the limits
+    pub fn new(config: &AutoScrollConfig) -> Self {
+        Self {
+            interval: Duration::from_millis(config.interval_ms)
+                .clamp(MIN_INTERVAL, MAX_INTERVAL),
+            by_paragraph: config.by == "paragraph",
+            paused: false,
+            last_scroll: Instant::now(),
+        }
+    }
+
+    /// true once per interval while running
+    pub fn is_due(&mut self) -> bool {
+        if self.paused || self.last_scroll.elapsed() < self.interval {
+            return false;
+        }
+        self.last_scroll = Instant::now();
+        true
+    }
+
+    /// scroll a fifth more often
+    pub fn faster(&mut self) {
+        self.interval = (self.interval * 4 / 5).max(MIN_INTERVAL);
+    }
+
+    /// scroll a fifth less often
+    pub fn slower(&mut self) {
+        self.interval = (self.interval * 5 / 4).min(MAX_INTERVAL);
+    }
+
+    /// stop or go on, the next scroll is an interval away
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.last_scroll = Instant::now();
+    }
+}
@@ -0,0 +1,144 @@
+//! backing up the library, highlights, history and settings to a tar
+//! archive and merging one back in
+
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    fs::{self, File},
+    io::Read,
+    path::Path,
+};
+
+use crate::library;
+use crate::paths::{Paths, DATA_FILES};
+
+const CACHE_DIR: &str = "chapters";
+
+/// write the library, highlights, history, search history and settings to a
+/// tar archive, returns the number of files written
+pub fn backup(
+    paths: &Paths,
+    out_path: &str,
+    with_cache: bool,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut builder = tar::Builder::new(File::create(out_path)?);
+    let mut count = 0;
+    let files = DATA_FILES
+        .iter()
+        .map(|name| (paths.data(name), *name))
+        .chain([(paths.config(), "config.toml")]);
+    for (path, name) in files {
+        if Path::new(&path).exists() {
+            builder.append_path_with_name(&path, name)?;
+            count += 1;
+        }
+    }
+    let cache = paths.chapters();
+    if with_cache && cache.is_dir() {
+        builder.append_dir_all(CACHE_DIR, &cache)?;
+        count += count_files(&cache);
+    }
+    builder.finish()?;
+    Ok(count)
+}
+
+/// what a restore added to the library
+#[derive(Default)]
+pub struct Restored {
+    /// novels in the backup, new or merged
+    pub novels: usize,
+    /// highlight, history and search history lines that were missing
+    pub lines: usize,
+    /// downloaded chapters that were missing
+    pub chapters: usize,
+}
+
+/// merge a backup into the library instead of replacing what is there:
+/// novels keep the newest progress, highlights and history lines are added
+/// if missing, settings and downloaded chapters are only taken if there are
+/// none yet
+pub fn restore(
+    paths: &Paths,
+    archive_path: &str,
+) -> Result<Restored, Box<dyn std::error::Error>> {
+    let mut archive = tar::Archive::new(File::open(archive_path)?);
+    let mut restored = Restored::default();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        match name.as_str() {
+            "novels.txt" => {
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                let backup = library::parse(&content);
+                restored.novels = backup.len();
+                library::modify(&paths.library(), |entries| {
+                    library::merge(entries, backup);
+                    // novels.txt is kept with the novel read last on top
+                    entries.sort_by_key(|x| Reverse(x.last_read));
+                    true
+                })?;
+            }
+            "highlights.txt" | "history.txt" | "search_history.txt" => {
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                restored.lines += merge_lines(&paths.data(&name), &content)?;
+            }
+            "config.toml" if !Path::new(&paths.config()).exists() => {
+                entry.unpack(paths.config())?;
+            }
+            _ if name.starts_with(CACHE_DIR)
+                && entry.header().entry_type().is_file()
+                && !paths.cache_dir.join(&name).exists() =>
+            {
+                // unpack_in refuses paths outside of the cache directory
+                restored.chapters +=
+                    entry.unpack_in(&paths.cache_dir)? as usize;
+            }
+            _ => {}
+        }
+    }
+    Ok(restored)
+}
+
+// append the lines of content that path doesn't have yet, returns how many
+fn merge_lines(
+    path: &str,
+    content: &str,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let mut seen = existing.lines().collect::<HashSet<_>>();
+    let mut merged = existing.clone();
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+    let mut count = 0;
+    for line in content.lines() {
+        if !line.trim().is_empty() && seen.insert(line) {
+            merged.push_str(line);
+            merged.push('\n');
+            count += 1;
+        }
+    }
+    fs::write(path, merged)?;
+    Ok(count)
+}
+
+fn count_files(dir: &Path) -> usize {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|x| {
+                    let path = x.path();
+                    if path.is_dir() {
+                        count_files(&path)
+                    } else {
+                        1
+                    }
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
@@ -0,0 +1,95 @@
+//! downloaded chapters, kept as the page html with one directory per
+//! novel: `<dir>/novel-name/chapter-12.html`
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::chapter::{self, Chapter};
+
+fn chapter_path(dir: &Path, url: &str) -> PathBuf {
+    let fields = url.split('/').collect::<Vec<_>>();
+    let novel = fields.get(3).unwrap_or(&"unknown");
+    let file = fields.last().unwrap_or(&"chapter.html");
+    dir.join(novel).join(file)
+}
+
+/// html of a downloaded chapter, None if it isn't downloaded
+pub fn load(dir: &Path, url: &str) -> Option<String> {
+    fs::read_to_string(chapter_path(dir, url)).ok()
+}
+
+/// downloaded chapters are read from the cache instead of the site
+pub async fn chapter(
+    dir: &Path,
+    url: &str,
+) -> Result<Chapter, Box<dyn std::error::Error>> {
+    match load(dir, url) {
+        Some(html) => chapter::from_html(url, &html),
+        None => chapter::fetch(url).await,
+    }
+}
+
+/// keep the html of a chapter, replacing an older copy
+pub fn store(dir: &Path, url: &str, html: &str) -> std::io::Result<()> {
+    let path = chapter_path(dir, url);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, html)
+}
+
+/// true if the chapter of url is downloaded
+pub fn contains(dir: &Path, url: &str) -> bool {
+    chapter_path(dir, url).exists()
+}
+
+/// number of chapters of a novel downloaded so far
+pub fn count(dir: &Path, novel: &str) -> usize {
+    fs::read_dir(dir.join(novel))
+        .map(|x| x.count())
+        .unwrap_or(0)
+}
+
+/// download a chapter into the cache unless it's there already
+pub async fn prefetch(
+    dir: &Path,
+    url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if contains(dir, url) {
+        return Ok(());
+    }
+    let html = chapter::fetch_html(url).await?;
+    // don't keep error pages
+    chapter::from_html(url, &html)?;
+    store(dir, url, &html)?;
+    Ok(())
+}
+
+/// remove the chapters downloaded longest ago until the cache is at most
+/// max_bytes, returns how many were removed
+pub fn prune(dir: &Path, max_bytes: u64) -> usize {
+    let mut files = Vec::new();
+    for novel in fs::read_dir(dir).into_iter().flatten().flatten() {
+        for file in fs::read_dir(novel.path()).into_iter().flatten().flatten() {
+            if let Ok(metadata) = file.metadata() {
+                let modified = metadata.modified().ok();
+                files.push((modified, metadata.len(), file.path()));
+            }
+        }
+    }
+    let mut size = files.iter().map(|x| x.1).sum::<u64>();
+    files.sort_by_key(|x| x.0);
+    let mut removed = 0;
+    for (_, length, path) in files {
+        if size <= max_bytes {
+            break;
+        }
+        if fs::remove_file(path).is_ok() {
+            size -= length;
+            removed += 1;
+        }
+    }
+    removed
+}
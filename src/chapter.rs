@@ -0,0 +1,385 @@
+//! the text of a chapter as styled blocks, parsed from its page
+
+use scraper::{ElementRef, Html, Node, Selector};
+
+use crate::net;
+
+/// how a span of text is shown
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    /// `<b>` or `<strong>`
+    pub bold: bool,
+    /// `<i>`, `<em>` or `<cite>`
+    pub italic: bool,
+    /// part of a heading, set when laying it out
+    pub heading: bool,
+    /// part of a blockquote, set when laying it out
+    pub quote: bool,
+    /// part of a saved highlight, marked by the reader and not by the page
+    pub highlight: bool,
+}
+
+impl Style {
+    /// the styles of both
+    pub fn merge(self, other: Style) -> Style {
+        Style {
+            bold: self.bold || other.bold,
+            italic: self.italic || other.italic,
+            heading: self.heading || other.heading,
+            quote: self.quote || other.quote,
+            highlight: self.highlight || other.highlight,
+        }
+    }
+}
+
+/// text in one style, whitespace already collapsed
+#[derive(Clone)]
+pub struct Span {
+    /// the text, with single spaces
+    pub text: String,
+    /// its style
+    pub style: Style,
+}
+
+/// a paragraph or another part of a chapter
+#[derive(Clone)]
+pub enum Block {
+    /// text of a `<p>`, or between line breaks
+    Paragraph(Vec<Span>),
+    /// `<h1>` to `<h6>`, usually the chapter title
+    Heading(Vec<Span>),
+    /// horizontal rules and scene breaks like "* * *"
+    Separator,
+    /// a `<blockquote>`, often letters or system messages
+    Blockquote(Vec<Span>),
+    /// a `<ul>` or `<ol>`, one list of spans per item
+    List(Vec<Vec<Span>>),
+}
+
+impl Block {
+    /// text without formatting, used for read aloud and highlights
+    pub fn text(&self) -> String {
+        match self {
+            Block::Paragraph(spans)
+            | Block::Heading(spans)
+            | Block::Blockquote(spans) => spans_text(spans),
+            Block::Separator => "* * *".to_string(),
+            Block::List(items) => items
+                .iter()
+                .map(|x| spans_text(x))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// a parsed chapter
+pub struct Chapter {
+    /// page the chapter was read from
+    pub url: String,
+    /// the text, in page order
+    pub blocks: Vec<Block>,
+}
+
+/// download and parse a chapter
+pub async fn fetch(url: &str) -> Result<Chapter, Box<dyn std::error::Error>> {
+    from_html(url, &fetch_html(url).await?)
+}
+
+/// html of a chapter page, to parse now or keep in the cache
+pub async fn fetch_html(
+    url: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(net::get(url).await?.text().await?)
+}
+
+/// parse a downloaded page, an error if it has no chapter text
+pub fn from_html(
+    url: &str,
+    html: &str,
+) -> Result<Chapter, Box<dyn std::error::Error>> {
+    match parse(html) {
+        Some(blocks) => Ok(Chapter {
+            url: url.to_string(),
+            blocks,
+        }),
+        None => Err(format!("no chapter found at {}", url).into()),
+    }
+}
+
+/// the blocks of the chapter text on a page, None if it has none
+pub fn parse(html: &str) -> Option<Vec<Block>> {
+    let fragment = Html::parse_fragment(html);
+
+    let selector_content = Selector::parse(r#"div[class="txt "]"#).unwrap();
+    let article = fragment.select(&selector_content).next()?;
+
+    let mut blocks = Vec::new();
+    // inline content that is not wrapped in a block element
+    let mut loose = Vec::new();
+    parse_blocks(article, &mut blocks, &mut loose);
+    flush(&mut loose, &mut blocks);
+    Some(blocks)
+}
+
+fn parse_blocks(
+    element: ElementRef,
+    blocks: &mut Vec<Block>,
+    loose: &mut Vec<Span>,
+) {
+    for child in element.children() {
+        let child = match child.value() {
+            Node::Text(text) => {
+                push_span(loose, text, Style::default());
+                continue;
+            }
+            Node::Element(_) => ElementRef::wrap(child).unwrap(),
+            _ => continue,
+        };
+
+        match child.value().name() {
+            "p" => {
+                flush(loose, blocks);
+                let mut spans = Vec::new();
+                collect_spans(child, Style::default(), &mut spans);
+                flush(&mut spans, blocks);
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                flush(loose, blocks);
+                let mut spans = Vec::new();
+                collect_spans(child, Style::default(), &mut spans);
+                trim_spans(&mut spans);
+                if !spans.is_empty() {
+                    blocks.push(Block::Heading(spans));
+                }
+            }
+            "hr" => {
+                flush(loose, blocks);
+                blocks.push(Block::Separator);
+            }
+            "blockquote" => {
+                flush(loose, blocks);
+                let mut spans = Vec::new();
+                collect_spans(child, Style::default(), &mut spans);
+                trim_spans(&mut spans);
+                if !spans.is_empty() {
+                    blocks.push(Block::Blockquote(spans));
+                }
+            }
+            "ul" | "ol" => {
+                flush(loose, blocks);
+                let selector = Selector::parse("li").unwrap();
+                let mut items = Vec::new();
+                for item in child.select(&selector) {
+                    let mut spans = Vec::new();
+                    collect_spans(item, Style::default(), &mut spans);
+                    trim_spans(&mut spans);
+                    if !spans.is_empty() {
+                        items.push(spans);
+                    }
+                }
+                if !items.is_empty() {
+                    blocks.push(Block::List(items));
+                }
+            }
+            // text separated by line breaks instead of paragraphs
+            "br" => flush(loose, blocks),
+            "div" | "section" | "article" => {
+                flush(loose, blocks);
+                parse_blocks(child, blocks, loose);
+                flush(loose, blocks);
+            }
+            "script" | "style" | "noscript" | "iframe" | "ins" => {}
+            name => collect_spans(child, style_for(name), loose),
+        }
+    }
+}
+
+fn collect_spans(element: ElementRef, style: Style, spans: &mut Vec<Span>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => push_span(spans, text, style),
+            Node::Element(element) => match element.name() {
+                "br" => push_span(spans, " ", style),
+                "script" | "style" | "noscript" | "iframe" | "ins" => {}
+                name => collect_spans(
+                    ElementRef::wrap(child).unwrap(),
+                    style.merge(style_for(name)),
+                    spans,
+                ),
+            },
+            _ => {}
+        }
+    }
+}
+
+fn style_for(name: &str) -> Style {
+    match name {
+        "em" | "i" | "cite" => Style {
+            italic: true,
+            ..Style::default()
+        },
+        "strong" | "b" => Style {
+            bold: true,
+            ..Style::default()
+        },
+        _ => Style::default(),
+    }
+}
+
+// add text with its whitespace collapsed, merging it into the last span if
+// the style is the same
+fn push_span(spans: &mut Vec<Span>, text: &str, style: Style) {
+    let mut collapsed = String::new();
+    for (index, word) in text.split_whitespace().enumerate() {
+        if index > 0 {
+            collapsed.push(' ');
+        }
+        collapsed.push_str(word);
+    }
+    if text.starts_with(char::is_whitespace) {
+        collapsed.insert(0, ' ');
+    }
+    if text.ends_with(char::is_whitespace) && collapsed != " " {
+        collapsed.push(' ');
+    }
+    if collapsed.is_empty() {
+        return;
+    }
+
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(&collapsed),
+        _ => spans.push(Span {
+            text: collapsed,
+            style,
+        }),
+    }
+}
+
+fn trim_spans(spans: &mut Vec<Span>) {
+    while spans.first().is_some_and(|x| x.text.trim().is_empty()) {
+        spans.remove(0);
+    }
+    while spans.last().is_some_and(|x| x.text.trim().is_empty()) {
+        spans.pop();
+    }
+    if let Some(first) = spans.first_mut() {
+        first.text = first.text.trim_start().to_string();
+    }
+    if let Some(last) = spans.last_mut() {
+        last.text = last.text.trim_end().to_string();
+    }
+}
+
+// turn collected inline spans into a paragraph or scene break
+fn flush(spans: &mut Vec<Span>, blocks: &mut Vec<Block>) {
+    trim_spans(spans);
+    if spans.is_empty() {
+        return;
+    }
+    if is_scene_break(&spans_text(spans)) {
+        blocks.push(Block::Separator);
+    } else {
+        blocks.push(Block::Paragraph(spans.clone()));
+    }
+    spans.clear();
+}
+
+// "***", "* * *", "~~~", "◇◇◇" and the like
+fn is_scene_break(text: &str) -> bool {
+    let marks = text
+        .chars()
+        .filter(|x| !x.is_whitespace())
+        .collect::<Vec<_>>();
+    marks.len() >= 3
+        && marks.len() <= 20
+        && marks.iter().all(|x| "*~-=_#◇◆○●•·※".contains(*x))
+}
+
+fn spans_text(spans: &[Span]) -> String {
+    spans.iter().map(|x| x.text.as_str()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks(body: &str) -> Vec<String> {
+        let html = format!(r#"<div class="txt ">{}</div>"#, body);
+        parse(&html)
+            .unwrap()
+            .iter()
+            .map(|x| match x {
+                Block::Paragraph(_) => format!("p {}", x.text()),
+                Block::Heading(_) => format!("h {}", x.text()),
+                Block::Separator => "hr".to_string(),
+                Block::Blockquote(_) => format!("quote {}", x.text()),
+                Block::List(_) => format!("list {}", x.text()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_needs_the_chapter_text() {
+        assert!(parse("<p>no chapter here</p>").is_none());
+    }
+
+    #[test]
+    fn parse_reads_each_kind_of_block() {
+        let body = "<h4>Chapter 1: The  Start</h4>\
+            <p>One <em>two</em> three.</p>\
+            <hr>\
+            <blockquote><p>A quote</p></blockquote>\
+            <ul><li>first</li><li> </li><li>second</li></ul>";
+        assert_eq!(
+            blocks(body),
+            [
+                "h Chapter 1: The Start",
+                "p One two three.",
+                "hr",
+                "quote A quote",
+                "list first\nsecond",
+            ]
+        );
+    }
+
+    #[test]
+    fn scene_breaks_become_separators() {
+        assert_eq!(
+            blocks("<p>before</p><p>* * *</p><p>◇◇◇</p><p>after</p>"),
+            ["p before", "hr", "hr", "p after"]
+        );
+    }
+
+    #[test]
+    fn empty_paragraphs_are_dropped() {
+        let body = "<p>one</p><p> </p><p>&nbsp;</p><p></p><h3> </h3>\
+            <blockquote></blockquote><ul><li></li></ul><p>two</p>";
+        assert_eq!(blocks(body), ["p one", "p two"]);
+    }
+
+    #[test]
+    fn text_between_line_breaks_makes_paragraphs() {
+        assert_eq!(
+            blocks("one<br>two <b>bold</b><br><br>three<script>x</script>"),
+            ["p one", "p two bold", "p three"]
+        );
+    }
+
+    #[test]
+    fn inline_styles_are_kept() {
+        let html = r#"<div class="txt "><p>a <b>b <i>c</i></b></p></div>"#;
+        let blocks = parse(html).unwrap();
+        let Block::Paragraph(spans) = &blocks[0] else {
+            panic!("not a paragraph");
+        };
+        let styles = spans
+            .iter()
+            .map(|x| (x.text.as_str(), x.style.bold, x.style.italic))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            styles,
+            [("a ", false, false), ("b ", true, false), ("c", true, true)]
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use std::{collections::BTreeMap, fs};
+
+use chrono::Local;
+
+use lightnovel_cli::backup;
+use lightnovel_cli::cache;
+use lightnovel_cli::cover;
+use lightnovel_cli::export::{self, Book, Format};
+use lightnovel_cli::import;
+use lightnovel_cli::library;
+use lightnovel_cli::novel;
+use lightnovel_cli::paths::Paths;
+
+pub const USAGE: &str = "usage:
+    lightnovel-cli                  open the reader
+    lightnovel-cli export <novel> [--from N] [--to N] [--format md|txt|html|fb2] [--out PATH] [--no-cover]
+    lightnovel-cli backup [--out PATH] [--with-cache]
+    lightnovel-cli restore <archive>
+    lightnovel-cli import <file>
+
+options:
+    --config-dir DIR                keep settings, library and downloads in DIR
+
+<novel> is a novel or chapter url, or the title of a novel in your library.
+LIGHTNOVEL_CLI_DIR does the same as --config-dir";
+
+// positional arguments and "--name value" options of a command
+pub struct Args {
+    pub positional: Vec<String>,
+    options: BTreeMap<String, String>,
+    switches: Vec<String>,
+}
+
+impl Args {
+    // switches are the options that don't take a value
+    pub fn parse(
+        args: &[String],
+        switches: &[&str],
+    ) -> Result<Args, Box<dyn std::error::Error>> {
+        let mut parsed = Args {
+            positional: vec![],
+            options: BTreeMap::new(),
+            switches: vec![],
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if switches.contains(&name) => {
+                    parsed.switches.push(name.to_string())
+                }
+                Some(name) => match args.next() {
+                    Some(value) => {
+                        parsed.options.insert(name.to_string(), value.clone());
+                    }
+                    None => {
+                        return Err(format!("--{} needs a value", name).into())
+                    }
+                },
+                None => parsed.positional.push(arg.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|x| x.as_str())
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.switches.iter().any(|x| x == name)
+    }
+
+    fn number(
+        &self,
+        name: &str,
+    ) -> Result<Option<i32>, Box<dyn std::error::Error>> {
+        match self.get(name) {
+            Some(value) => match value.parse::<i32>() {
+                Ok(number) => Ok(Some(number)),
+                Err(_) => Err(format!("--{} must be a number", name).into()),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+pub async fn export(
+    args: &[String],
+    paths: &Paths,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse(args, &["no-cover"])?;
+
+    let format = match args.get("format") {
+        Some(name) => match Format::parse(name) {
+            Some(format) => format,
+            None => return Err(format!("unknown format '{}'", name).into()),
+        },
+        None => Format::Markdown,
+    };
+    let (slug, mut book) = read_chapters(&args, paths).await?;
+    // only FictionBook files embed the cover
+    if format == Format::Fb2
+        && !args.has("no-cover")
+        && !book.info.cover_url.is_empty()
+    {
+        book.cover = cover::fetch(&book.info.cover_url).await.ok();
+    }
+    let out_path = match args.get("out") {
+        Some(out_path) => out_path.to_string(),
+        None => format.default_path(&slug),
+    };
+    export::write(format, &book, &out_path)?;
+    println!("exported {} chapters to {}", book.chapters.len(), out_path);
+    Ok(())
+}
+
+pub fn backup(
+    args: &[String],
+    paths: &Paths,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse(args, &["with-cache"])?;
+    let out_path = match args.get("out") {
+        Some(out_path) => out_path.to_string(),
+        None => format!(
+            "./lightnovel-backup-{}.tar",
+            Local::now().format("%Y-%m-%d")
+        ),
+    };
+    let count = backup::backup(paths, &out_path, args.has("with-cache"))?;
+    println!("backed up {} files to {}", count, out_path);
+    Ok(())
+}
+
+pub fn restore(
+    args: &[String],
+    paths: &Paths,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse(args, &[])?;
+    let archive_path = match args.positional.first() {
+        Some(archive_path) => archive_path,
+        None => return Err(USAGE.into()),
+    };
+    let restored = backup::restore(paths, archive_path)?;
+    println!(
+        "restored {} novels, {} highlight and history lines and {} chapters",
+        restored.novels, restored.lines, restored.chapters
+    );
+    Ok(())
+}
+
+// add the novels of another app's backup, a csv or a list of urls to the
+// library the same way opening them in the reader does
+pub async fn import(
+    args: &[String],
+    paths: &Paths,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse(args, &[])?;
+    let file = match args.positional.first() {
+        Some(file) => file,
+        None => return Err(USAGE.into()),
+    };
+    let path = &paths.library();
+    let mut novels = import::parse(&fs::read_to_string(file)?)?;
+    // oldest first so the last read novel ends up at the top
+    novels.sort_by_key(|x| x.last_read);
+
+    let (mut added, mut skipped) = (0, 0);
+    for novel in novels.iter() {
+        let url = match import::source_url(novel).await {
+            Ok(url) => url,
+            Err(err) => {
+                eprintln!("skipping {}: {}", novel.title, err);
+                skipped += 1;
+                continue;
+            }
+        };
+        let title = library::title_from_url(&url).unwrap_or_default();
+        let existing =
+            library::load(path)?.into_iter().find(|x| x.title == title);
+        // don't move anyone back to an earlier chapter
+        if existing.is_some_and(|x| x.chapter() >= novel.chapter.unwrap_or(1)) {
+            println!("{} is already in the library", title);
+            skipped += 1;
+            continue;
+        }
+        let last_read = match novel.last_read {
+            0 => library::now(),
+            last_read => last_read,
+        };
+        library::add(path, &url, last_read)?;
+        println!("added {}", title);
+        added += 1;
+    }
+    println!("imported {} novels, skipped {}", added, skipped);
+    Ok(())
+}
+
+// chapters --from to --to (every chapter by default) of the novel named by
+// the first argument, downloaded ones are taken from the cache
+async fn read_chapters(
+    args: &Args,
+    paths: &Paths,
+) -> Result<(String, Book), Box<dyn std::error::Error>> {
+    let name = match args.positional.first() {
+        Some(name) => name,
+        None => return Err(USAGE.into()),
+    };
+    let (from, to) = (args.number("from")?, args.number("to")?);
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(format!("--from {} is after --to {}", from, to).into());
+        }
+    }
+    let url = novel_url(name, &paths.library())?;
+    let slug = url
+        .split('/')
+        .nth(3)
+        .unwrap_or("novel")
+        .trim_end_matches(".html")
+        .to_string();
+    let info = novel::fetch(&url).await?;
+    if info.chapters == 0 {
+        return Err(format!("no chapters found at {}", url).into());
+    }
+
+    let from = from.unwrap_or(1).max(1);
+    let to = to.unwrap_or(info.chapters).min(info.chapters);
+    if from > to {
+        return Err(format!(
+            "--from {} is past the last chapter, {}",
+            from, info.chapters
+        )
+        .into());
+    }
+    let cache_dir = paths.chapters();
+    let mut chapters = Vec::new();
+    for number in from..=to {
+        eprint!("\rreading chapter {} of {}", number, to);
+        let url = match novel::chapter_url(&info, number) {
+            Some(url) => url,
+            None => return Err("the novel has no chapter urls".into()),
+        };
+        match cache::chapter(&cache_dir, &url).await {
+            Ok(chapter) => chapters.push(chapter),
+            Err(err) => eprintln!("\nskipping chapter {}: {}", number, err),
+        }
+    }
+    eprintln!();
+    let book = Book {
+        url,
+        info,
+        chapters,
+        cover: None,
+    };
+    Ok((slug, book))
+}
+
+// landing page of a novel from a novel url, a chapter url or a title in the
+// library
+fn novel_url(
+    name: &str,
+    path: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if name.starts_with("http") {
+        // "https://freewebnovel.com/novel-name.html" is already the landing
+        // page, chapter urls have one more part
+        if name.split('/').count() > 4 {
+            return Ok(novel::url_from_chapter(name));
+        }
+        return Ok(name.to_string());
+    }
+    let name = name.to_lowercase();
+    match library::load(path)?.into_iter().find(|x| {
+        x.title.to_lowercase() == name
+            || x.display_title().to_lowercase() == name
+    }) {
+        Some(entry) => Ok(novel::url_from_chapter(&entry.url)),
+        None => {
+            Err(format!("no novel called '{}' in the library", name).into())
+        }
+    }
+}
@@ -0,0 +1,502 @@
+//! settings read from and written back to `config.toml`
+
+use serde::{Deserialize, Serialize};
+use std::{fs, io};
+
+use crate::net;
+
+/// every setting, missing ones take their default
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct Config {
+    /// `[source]`
+    pub source: SourceConfig,
+    /// `[reader]`
+    pub reader: ReaderConfig,
+    /// `[tts]`
+    pub tts: TtsConfig,
+    /// `[autoscroll]`
+    pub autoscroll: AutoScrollConfig,
+    /// `[library]`
+    pub library: LibraryConfig,
+    /// `[cache]`
+    pub cache: CacheConfig,
+    /// `[network]`
+    pub network: NetworkConfig,
+}
+
+/// where novels are read from
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct SourceConfig {
+    /// address of the site, a mirror with the same layout works too
+    pub site: String,
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        Self {
+            site: net::DEFAULT_SITE.to_string(),
+        }
+    }
+}
+
+/// how chapters are shown
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReaderConfig {
+    /// load the next and previous chapters when scrolling past the edges
+    pub continuous: bool,
+    /// columns of text at most, 0 leaves an eighth of the screen on each side
+    pub width: usize,
+    /// "percent", "chapter", "both" or "none" in the top right corner
+    pub progress: String,
+    /// chapters after the open one downloaded in the background
+    pub prefetch: u32,
+}
+
+impl Default for ReaderConfig {
+    fn default() -> Self {
+        Self {
+            continuous: false,
+            width: 0,
+            progress: "percent".to_string(),
+            prefetch: 1,
+        }
+    }
+}
+
+/// the program reading aloud
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct TtsConfig {
+    /// program that reads the paragraph from stdin and speaks it
+    pub command: String,
+    /// "{speed}" is replaced with the current speed in words per minute
+    pub args: Vec<String>,
+    /// words per minute to start at
+    pub speed: u32,
+}
+
+impl Default for TtsConfig {
+    fn default() -> Self {
+        Self {
+            command: "espeak-ng".to_string(),
+            args: vec!["-s".to_string(), "{speed}".to_string()],
+            speed: 175,
+        }
+    }
+}
+
+/// scrolling on a timer
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct AutoScrollConfig {
+    /// milliseconds between two scrolls
+    pub interval_ms: u64,
+    /// "line" or "paragraph"
+    pub by: String,
+}
+
+impl Default for AutoScrollConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 2000,
+            by: "line".to_string(),
+        }
+    }
+}
+
+/// the library screen
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct LibraryConfig {
+    /// "last-read", "title", "unread", "added" or "progress"
+    pub sort: String,
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        Self {
+            sort: "last-read".to_string(),
+        }
+    }
+}
+
+/// downloaded chapters
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// downloaded chapters are removed oldest first past this, 0 keeps all
+    pub max_mb: u64,
+}
+
+/// http requests
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// seconds before a request is given up, at least one
+    pub timeout_secs: u64,
+    /// attempts after a request that failed to connect or timed out
+    pub retries: u32,
+    /// empty for the default
+    pub user_agent: String,
+    /// e.g. "http://127.0.0.1:8080", empty for none
+    pub proxy: String,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            retries: 2,
+            user_agent: String::new(),
+            proxy: String::new(),
+        }
+    }
+}
+
+// settings that take one of a few values, cycled on the settings screen
+const CHOICES: [(&str, &[&str]); 3] = [
+    ("reader.progress", &["percent", "chapter", "both", "none"]),
+    ("autoscroll.by", &["line", "paragraph"]),
+    (
+        "library.sort",
+        &["last-read", "title", "unread", "added", "progress"],
+    ),
+];
+
+/// "section.key" and value of every setting
+pub fn entries(config: &Config) -> Vec<(String, toml::Value)> {
+    let mut entries = Vec::new();
+    if let Ok(toml::Value::Table(sections)) = toml::Value::try_from(config) {
+        for (section, table) in sections {
+            if let toml::Value::Table(table) = table {
+                for (key, value) in table {
+                    entries.push((format!("{}.{}", section, key), value));
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// values a setting can take, None if it takes any
+pub fn choices(key: &str) -> Option<&'static [&'static str]> {
+    CHOICES.iter().find(|x| x.0 == key).map(|x| x.1)
+}
+
+/// value as typed on the settings screen, strings without quotes
+pub fn format_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// change one setting, text is parsed as the type the setting already has
+pub fn set(
+    config: &mut Config,
+    key: &str,
+    text: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (section, name) = key.split_once('.').ok_or("unknown setting")?;
+    let mut value = toml::Value::try_from(&*config)?;
+    let old = value
+        .get_mut(section)
+        .and_then(|x| x.get_mut(name))
+        .ok_or("unknown setting")?;
+    let new = match old {
+        toml::Value::String(_) => toml::Value::String(text.to_string()),
+        _ => {
+            let table: toml::value::Table =
+                toml::from_str(&format!("value = {}", text.trim())).map_err(
+                    |_| format!("{} must be a {}", key, old.type_str()),
+                )?;
+            table["value"].clone()
+        }
+    };
+    if new.type_str() != old.type_str() {
+        return Err(format!("{} must be a {}", key, old.type_str()).into());
+    }
+    if let Some(choices) = choices(key) {
+        if !choices.contains(&text) {
+            return Err(format!("{} must be {}", key, choices.join("/")).into());
+        }
+    }
+    *old = new;
+    *config = value.try_into()?;
+    Ok(())
+}
+
+/// a missing config file is all defaults, one that doesn't parse is an
+/// error so it isn't saved over
+pub fn load(path: &str) -> Result<Config, Box<dyn std::error::Error>> {
+    match fs::read_to_string(path) {
+        Ok(content) => toml::from_str(&content)
+            .map_err(|err| format!("error in {}: {}", path, err).into()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            Ok(Config::default())
+        }
+        Err(err) => Err(format!("can't read {}: {}", path, err).into()),
+    }
+}
+
+/// write the value key has in config to the file at path, every other line
+/// and comment stays as it is. a file that doesn't parse is left alone
+pub fn save_setting(
+    path: &str,
+    key: &str,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+    fs::write(path, with_setting(&content, path, key, config)?)?;
+    Ok(())
+}
+
+// content with the line of key, or the lines of a value over several lines,
+// replaced by the value key has in config, or with it added to its section
+fn with_setting(
+    content: &str,
+    path: &str,
+    key: &str,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    toml::from_str::<Config>(content)
+        .map_err(|err| format!("fix {} first: {}", path, err))?;
+    let (section, name) = key.split_once('.').ok_or("unknown setting")?;
+    let value = entries(config)
+        .into_iter()
+        .find(|x| x.0 == key)
+        .map(|x| x.1)
+        .ok_or("unknown setting")?;
+    let setting = format!("{} = {}", name, value);
+
+    let mut lines = content.lines().map(|x| x.to_string()).collect::<Vec<_>>();
+    let mut current = String::new();
+    // last line of the section that isn't blank, and the first and last
+    // line of the key
+    let (mut end, mut found) = (None, None);
+    // brackets of a value still open, its next lines aren't keys or headers
+    let mut open = 0;
+    let mut in_found = false;
+    for (index, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if open > 0 {
+            open += depth(line);
+            if current == section {
+                end = Some(index);
+            }
+            if let (true, Some((first, _))) = (in_found, found) {
+                found = Some((first, index));
+            }
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            current = header.split(']').next().unwrap_or("").trim().into();
+            if current == section {
+                end = Some(index);
+            }
+            continue;
+        }
+        open = depth(line);
+        if current == section {
+            if !line.is_empty() {
+                end = Some(index);
+            }
+            if line.split('=').next().map(|x| x.trim()) == Some(name) {
+                found = Some((index, index));
+            }
+        }
+        in_found = open > 0 && found.is_some_and(|x| x.0 == index);
+    }
+    match (found, end) {
+        (Some((first, last)), _) => {
+            let comment = trailing_comment(&lines[first]).to_string();
+            let indent = lines[first].len() - lines[first].trim_start().len();
+            let line =
+                format!("{}{}{}", &lines[first][..indent], setting, comment);
+            lines.splice(first..=last, [line]);
+        }
+        (None, Some(index)) => lines.insert(index + 1, setting),
+        (None, None) => {
+            if lines.last().is_some_and(|x| !x.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", section));
+            lines.push(setting);
+        }
+    }
+    let content = lines.join("\n") + "\n";
+
+    // a setting written some other way, like an inline table, is left to be
+    // changed by hand rather than written twice
+    let saved = toml::from_str::<Config>(&content)
+        .ok()
+        .and_then(|config| entries(&config).into_iter().find(|x| x.0 == key))
+        .map(|x| x.1);
+    if saved != Some(value) {
+        return Err(format!("change {} in {} by hand", key, path).into());
+    }
+    Ok(content)
+}
+
+// index of the '#' starting a comment at the end of the line, a '#' inside
+// a string doesn't count
+fn comment_start(line: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, ch) in line.char_indices() {
+        match (quote, ch) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), ch) if ch == open && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '#') => return Some(index),
+            _ => {}
+        }
+        escaped = false;
+    }
+    None
+}
+
+// " # comment" at the end of a line
+fn trailing_comment(line: &str) -> &str {
+    match comment_start(line) {
+        Some(index) => &line[line[..index].trim_end().len()..],
+        None => "",
+    }
+}
+
+// brackets the line opens minus the ones it closes, outside of strings and
+// comments
+fn depth(line: &str) -> i32 {
+    let code = &line[..comment_start(line).unwrap_or(line.len())];
+    let mut quote = None;
+    let mut escaped = false;
+    let mut depth = 0;
+    for ch in code.chars() {
+        match (quote, ch) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), ch) if ch == open && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '[') => depth += 1,
+            (None, ']') => depth -= 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // content after setting key to text
+    fn saved(content: &str, key: &str, text: &str) -> Result<String, String> {
+        let mut config: Config = toml::from_str(content).unwrap();
+        set(&mut config, key, text).unwrap();
+        with_setting(content, "config.toml", key, &config)
+            .map_err(|x| x.to_string())
+    }
+
+    #[test]
+    fn changes_only_the_line_of_the_key() {
+        let content = "# my settings\n\n\
+                       [reader]\nwidth = 80\nprefetch = 1\n\n\
+                       [tts]\nspeed = 175\n";
+        assert_eq!(
+            saved(content, "reader.width", "100").unwrap(),
+            "# my settings\n\n\
+             [reader]\nwidth = 100\nprefetch = 1\n\n\
+             [tts]\nspeed = 175\n"
+        );
+    }
+
+    #[test]
+    fn a_key_of_the_same_name_in_another_section_stays() {
+        let content = "[autoscroll]\nby = \"line\"\n\n\
+                       [tts]\nspeed = 175\n\n\
+                       [reader]\nprefetch = 1\n";
+        let content = saved(content, "tts.speed", "200").unwrap();
+        assert!(content.contains("[tts]\nspeed = 200\n"));
+        // a key missing from its section goes after the section's last line
+        let content = saved(&content, "autoscroll.interval_ms", "500").unwrap();
+        assert_eq!(
+            content,
+            "[autoscroll]\nby = \"line\"\ninterval_ms = 500\n\n\
+             [tts]\nspeed = 200\n\n\
+             [reader]\nprefetch = 1\n"
+        );
+    }
+
+    #[test]
+    fn a_missing_section_is_appended() {
+        assert_eq!(
+            saved("[reader]\nwidth = 80", "tts.speed", "200").unwrap(),
+            "[reader]\nwidth = 80\n\n[tts]\nspeed = 200\n"
+        );
+        assert_eq!(
+            saved("", "tts.speed", "200").unwrap(),
+            "[tts]\nspeed = 200\n"
+        );
+    }
+
+    #[test]
+    fn trailing_comments_and_indentation_stay() {
+        let content =
+            "[tts]\n  command = \"say # hi\" # the # in the string stays\n";
+        assert_eq!(
+            saved(content, "tts.command", "espeak").unwrap(),
+            "[tts]\n  command = \"espeak\" # the # in the string stays\n"
+        );
+    }
+
+    #[test]
+    fn a_value_over_several_lines_is_replaced_whole() {
+        let content =
+            "[tts]\nargs = [ # flags\n  \"-s\",\n  \"{speed}\", # ]\n\
+                       ]\nspeed = 175\n";
+        assert_eq!(
+            saved(content, "tts.args", "[\"-v\", \"en\"]").unwrap(),
+            "[tts]\nargs = [\"-v\", \"en\"] # flags\nspeed = 175\n"
+        );
+        // the lines of an array of another key aren't taken for keys
+        let content = "[tts]\nargs = [\n  \"speed = 1\",\n]\n";
+        assert_eq!(
+            saved(content, "tts.speed", "200").unwrap(),
+            "[tts]\nargs = [\n  \"speed = 1\",\n]\nspeed = 200\n"
+        );
+    }
+
+    #[test]
+    fn a_broken_file_is_left_alone() {
+        let err = with_setting(
+            "[tts\n",
+            "config.toml",
+            "tts.speed",
+            &Config::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("fix config.toml first"));
+    }
+
+    #[test]
+    fn a_setting_written_another_way_is_left_to_change_by_hand() {
+        let content = "tts = { speed = 175 }\n";
+        assert_eq!(
+            saved(content, "tts.speed", "200").unwrap_err(),
+            "change tts.speed in config.toml by hand"
+        );
+    }
+}
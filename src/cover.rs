@@ -0,0 +1,40 @@
+//! fetching cover images and drawing them as text
+
+use image::imageops::FilterType;
+
+use crate::net;
+
+// darkest to brightest
+const SHADES: &[u8] = b" .:-=+*#%@";
+
+/// the image at url
+pub async fn fetch(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let resp = net::get(url).await?;
+    Ok(resp.bytes().await?.to_vec())
+}
+
+/// draw the cover with characters of increasing density, width columns wide
+/// and at most height rows, None if the image can't be decoded
+pub fn text_art(bytes: &[u8], width: u32, height: u32) -> Option<Vec<String>> {
+    let image = image::load_from_memory(bytes).ok()?;
+    if image.width() == 0 || image.height() == 0 || width == 0 {
+        return None;
+    }
+    // a terminal cell is about twice as tall as it is wide
+    let rows = (width * image.height() / image.width() / 2).clamp(1, height);
+    let gray = image
+        .resize_exact(width, rows, FilterType::Triangle)
+        .to_luma8();
+
+    let mut lines = Vec::new();
+    for y in 0..rows {
+        let line = (0..width)
+            .map(|x| {
+                let value = gray.get_pixel(x, y).0[0] as usize;
+                SHADES[value * (SHADES.len() - 1) / 255] as char
+            })
+            .collect::<String>();
+        lines.push(line);
+    }
+    Some(lines)
+}
@@ -0,0 +1,369 @@
+//! writing chapters as Markdown, plain text, HTML or FictionBook
+
+use std::{fs, io, path::Path};
+
+use crate::chapter::{Block, Chapter, Span};
+use crate::fb2;
+use crate::novel::{self, NovelInfo};
+
+/// everything an export needs to know about a novel
+pub struct Book {
+    /// landing page of the novel
+    pub url: String,
+    /// what the landing page says about the novel
+    pub info: NovelInfo,
+    /// chapters to write, in reading order
+    pub chapters: Vec<Chapter>,
+    /// image bytes, only written to FictionBook
+    pub cover: Option<Vec<u8>>,
+}
+
+/// the kinds of file a novel can be written to
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// one file with every chapter
+    Markdown,
+    /// a directory with a file per chapter
+    Text,
+    /// a directory with an index page and a page per chapter
+    Html,
+    /// one FictionBook file
+    Fb2,
+}
+
+impl Format {
+    /// the name of a format as given on the command line
+    pub fn parse(text: &str) -> Option<Format> {
+        match text {
+            "md" | "markdown" => Some(Format::Markdown),
+            "txt" | "text" => Some(Format::Text),
+            "html" => Some(Format::Html),
+            "fb2" => Some(Format::Fb2),
+            _ => None,
+        }
+    }
+
+    /// output path used when none is given
+    pub fn default_path(self, slug: &str) -> String {
+        match self {
+            Format::Markdown => format!("./{}.md", slug),
+            Format::Fb2 => format!("./{}.fb2", slug),
+            Format::Text | Format::Html => format!("./{}", slug),
+        }
+    }
+}
+
+/// write the book in format to out_path, a file or directory
+pub fn write(format: Format, book: &Book, out_path: &str) -> io::Result<()> {
+    let title = &book.info.title;
+    match format {
+        Format::Markdown => {
+            fs::write(out_path, markdown(title, &book.chapters))
+        }
+        Format::Text => write_text(&book.chapters, out_path),
+        Format::Html => write_html(title, &book.chapters, out_path),
+        Format::Fb2 => fs::write(out_path, fb2::document(book)),
+    }
+}
+
+/// the heading at the start of the chapter if it has one, "Chapter N" if not
+pub fn chapter_title(chapter: &Chapter) -> String {
+    match chapter.blocks.first() {
+        Some(Block::Heading(spans)) => plain(spans),
+        _ => match novel::chapter_number(&chapter.url) {
+            Some(number) => format!("Chapter {}", number),
+            None => "Chapter".to_string(),
+        },
+    }
+}
+
+/// blocks after the heading chapter_title took
+pub fn chapter_body(chapter: &Chapter) -> &[Block] {
+    match chapter.blocks.first() {
+        Some(Block::Heading(_)) => &chapter.blocks[1..],
+        _ => &chapter.blocks,
+    }
+}
+
+/// the chapters as one Markdown document with title as heading
+pub fn markdown(title: &str, chapters: &[Chapter]) -> String {
+    let mut content = format!("# {}\n", escape_markdown(title));
+    for chapter in chapters {
+        let heading = escape_markdown(&chapter_title(chapter));
+        content.push_str(&format!("\n## {}\n", heading));
+        for block in chapter_body(chapter) {
+            content.push('\n');
+            match block {
+                Block::Paragraph(spans) => {
+                    content.push_str(&escape_line_start(markdown_spans(spans)))
+                }
+                Block::Heading(spans) => {
+                    content.push_str(&format!("### {}", markdown_spans(spans)))
+                }
+                Block::Separator => content.push_str("* * *"),
+                Block::Blockquote(spans) => {
+                    let quote = escape_line_start(markdown_spans(spans));
+                    content.push_str(&format!("> {}", quote))
+                }
+                Block::List(items) => {
+                    let items = items
+                        .iter()
+                        .map(|x| format!("- {}", markdown_spans(x)))
+                        .collect::<Vec<_>>();
+                    content.push_str(&items.join("\n"));
+                }
+            }
+            content.push('\n');
+        }
+    }
+    content
+}
+
+fn markdown_spans(spans: &[Span]) -> String {
+    let mut text = String::new();
+    for span in spans {
+        // markers can't have spaces on the inside, keep them outside
+        let inner = escape_markdown(span.text.trim());
+        if inner.is_empty() || !(span.style.bold || span.style.italic) {
+            text.push_str(&escape_markdown(&span.text));
+            continue;
+        }
+        let marker = match (span.style.bold, span.style.italic) {
+            (true, true) => "***",
+            (true, false) => "**",
+            _ => "*",
+        };
+        if span.text.starts_with(' ') {
+            text.push(' ');
+        }
+        text.push_str(&format!("{}{}{}", marker, inner, marker));
+        if span.text.ends_with(' ') {
+            text.push(' ');
+        }
+    }
+    text
+}
+
+// a backslash before everything Markdown could read as formatting, so
+// "*sigh*" or "#1" in the text comes out as written
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '\\' | '`' | '*' | '_' | '#' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+// a paragraph starting with "- ", "+ " or "1. " would become a list
+fn escape_line_start(line: String) -> String {
+    let digits = line.chars().take_while(|x| x.is_ascii_digit()).count();
+    match line[digits..].chars().next() {
+        Some('.' | ')') if digits > 0 => {
+            format!("{}\\{}", &line[..digits], &line[digits..])
+        }
+        Some('-' | '+' | '=') if digits == 0 => format!("\\{}", line),
+        _ => line,
+    }
+}
+
+// chapter-0001.txt, chapter-0002.txt, ... sorted the same way by name
+// as by chapter
+fn write_text(chapters: &[Chapter], out_dir: &str) -> io::Result<()> {
+    fs::create_dir_all(out_dir)?;
+    for (index, chapter) in chapters.iter().enumerate() {
+        let mut content = format!("{}\n", chapter_title(chapter));
+        for block in chapter_body(chapter) {
+            content.push('\n');
+            match block {
+                Block::Blockquote(spans) => {
+                    content.push_str(&format!("    {}", plain(spans)))
+                }
+                Block::List(items) => {
+                    let items = items
+                        .iter()
+                        .map(|x| format!("- {}", plain(x)))
+                        .collect::<Vec<_>>();
+                    content.push_str(&items.join("\n"));
+                }
+                block => content.push_str(&block.text()),
+            }
+            content.push('\n');
+        }
+        let name = format!("{}.txt", file_stem(chapter, index));
+        fs::write(Path::new(out_dir).join(name), content)?;
+    }
+    Ok(())
+}
+
+fn write_html(
+    title: &str,
+    chapters: &[Chapter],
+    out_dir: &str,
+) -> io::Result<()> {
+    fs::create_dir_all(out_dir)?;
+    let names = chapters
+        .iter()
+        .enumerate()
+        .map(|(index, chapter)| format!("{}.html", file_stem(chapter, index)))
+        .collect::<Vec<_>>();
+
+    let mut index_body = format!("<h1>{}</h1>\n<ol>\n", escape(title));
+    for (chapter, name) in chapters.iter().zip(names.iter()) {
+        index_body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            name,
+            escape(&chapter_title(chapter))
+        ));
+    }
+    index_body.push_str("</ol>\n");
+    fs::write(
+        Path::new(out_dir).join("index.html"),
+        html_page(title, &index_body),
+    )?;
+
+    for (index, chapter) in chapters.iter().enumerate() {
+        let mut nav = String::from("<nav>");
+        if index > 0 {
+            nav.push_str(&format!(
+                "<a href=\"{}\">previous</a> ",
+                names[index - 1]
+            ));
+        }
+        nav.push_str("<a href=\"index.html\">index</a>");
+        if index + 1 < chapters.len() {
+            nav.push_str(&format!(
+                " <a href=\"{}\">next</a>",
+                names[index + 1]
+            ));
+        }
+        nav.push_str("</nav>\n");
+
+        let chapter_title = chapter_title(chapter);
+        let mut body = nav.clone();
+        body.push_str(&format!("<h1>{}</h1>\n", escape(&chapter_title)));
+        for block in chapter_body(chapter) {
+            body.push_str(&html_block(block));
+        }
+        body.push_str(&nav);
+        fs::write(
+            Path::new(out_dir).join(&names[index]),
+            html_page(&format!("{} - {}", chapter_title, title), &body),
+        )?;
+    }
+    Ok(())
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{}</title>
+<style>
+body {{ max-width: 40em; margin: auto; padding: 1em; line-height: 1.6; font-family: serif; }}
+nav {{ text-align: center; margin: 1em 0; }}
+blockquote {{ border-left: 3px solid #888; margin-left: 0; padding-left: 1em; }}
+hr {{ border: none; text-align: center; }}
+hr::after {{ content: \"* * *\"; }}
+</style>
+</head>
+<body>
+{}</body>
+</html>
+",
+        escape(title),
+        body
+    )
+}
+
+/// a block as an html element
+pub fn html_block(block: &Block) -> String {
+    match block {
+        Block::Paragraph(spans) => format!("<p>{}</p>\n", html_spans(spans)),
+        Block::Heading(spans) => format!("<h2>{}</h2>\n", html_spans(spans)),
+        Block::Separator => "<hr>\n".to_string(),
+        Block::Blockquote(spans) => {
+            format!("<blockquote>{}</blockquote>\n", html_spans(spans))
+        }
+        Block::List(items) => {
+            let items = items
+                .iter()
+                .map(|x| format!("<li>{}</li>", html_spans(x)))
+                .collect::<String>();
+            format!("<ul>{}</ul>\n", items)
+        }
+    }
+}
+
+fn html_spans(spans: &[Span]) -> String {
+    let mut html = String::new();
+    for span in spans {
+        let mut text = escape(&span.text);
+        if span.style.italic {
+            text = format!("<em>{}</em>", text);
+        }
+        if span.style.bold {
+            text = format!("<strong>{}</strong>", text);
+        }
+        html.push_str(&text);
+    }
+    html
+}
+
+/// text safe to put in html or xml
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// the text of spans without their styles
+pub fn plain(spans: &[Span]) -> String {
+    spans.iter().map(|x| x.text.as_str()).collect()
+}
+
+fn file_stem(chapter: &Chapter, index: usize) -> String {
+    let number =
+        novel::chapter_number(&chapter.url).unwrap_or(index as i32 + 1);
+    format!("chapter-{:04}", number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> String {
+        escape_line_start(escape_markdown(text))
+    }
+
+    #[test]
+    fn formatting_characters_are_escaped() {
+        assert_eq!(line("*sigh*"), "\\*sigh\\*");
+        assert_eq!(line("snake_case"), "snake\\_case");
+        assert_eq!(line("`code`"), "\\`code\\`");
+        assert_eq!(line("[1] note"), "\\[1\\] note");
+        assert_eq!(line("a \\ b"), "a \\\\ b");
+    }
+
+    #[test]
+    fn line_starts_are_escaped() {
+        assert_eq!(line("# not a heading"), "\\# not a heading");
+        assert_eq!(line("> not a quote"), "\\> not a quote");
+        assert_eq!(line("- not a list"), "\\- not a list");
+        assert_eq!(line("+ not a list"), "\\+ not a list");
+        assert_eq!(line("1. not a list"), "1\\. not a list");
+        assert_eq!(line("12) not a list"), "12\\) not a list");
+    }
+
+    #[test]
+    fn plain_text_stays_as_it_is() {
+        assert_eq!(line("It was 1999, - he said."), "It was 1999, - he said.");
+        assert_eq!(line("2024 was a year"), "2024 was a year");
+        assert_eq!(line(""), "");
+    }
+}
@@ -0,0 +1,239 @@
+//! FictionBook 2 documents
+
+use chrono::Local;
+use std::io::Cursor;
+
+use crate::chapter::{Block, Span};
+use crate::export::{chapter_body, chapter_title, escape, Book};
+
+// genres of the site and the closest FictionBook genre code
+const GENRES: [(&str, &str); 14] = [
+    ("Fantasy", "sf_fantasy"),
+    ("Xianxia", "sf_fantasy"),
+    ("Xuanhuan", "sf_fantasy"),
+    ("Wuxia", "sf_fantasy"),
+    ("Martial Arts", "sf_action"),
+    ("Action", "sf_action"),
+    ("Sci-fi", "sf"),
+    ("Mecha", "sf"),
+    ("Horror", "sf_horror"),
+    ("Romance", "love"),
+    ("Comedy", "humor"),
+    ("Mystery", "detective"),
+    ("Historical", "sf_history"),
+    ("Adventure", "adventure"),
+];
+
+/// a FictionBook 2 document with the cover embedded if there is one
+pub fn document(book: &Book) -> String {
+    let info = &book.info;
+    let cover = book.cover.as_deref().and_then(cover_image);
+    let mut fb2 = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<FictionBook xmlns=\"http://www.gribuser.ru/xml/fictionbook/2.0\" xmlns:l=\"http://www.w3.org/1999/xlink\">
+<description>
+<title-info>
+",
+    );
+    // several site genres share a code, keep each once in the site's order
+    let mut genres = Vec::new();
+    for (_, code) in info
+        .genres
+        .iter()
+        .filter_map(|x| GENRES.iter().find(|(name, _)| name == x))
+    {
+        if !genres.contains(code) {
+            genres.push(*code);
+        }
+    }
+    if genres.is_empty() {
+        genres.push("prose");
+    }
+    for genre in genres {
+        fb2.push_str(&format!("<genre>{}</genre>\n", genre));
+    }
+    fb2.push_str(&author(&info.author));
+    fb2.push_str(&format!(
+        "<book-title>{}</book-title>\n",
+        escape(&info.title)
+    ));
+    if !info.synopsis.is_empty() {
+        fb2.push_str("<annotation>\n");
+        for paragraph in info.synopsis.lines() {
+            fb2.push_str(&format!("<p>{}</p>\n", escape(paragraph)));
+        }
+        fb2.push_str("</annotation>\n");
+    }
+    if let Some((name, _, _)) = &cover {
+        fb2.push_str(&format!(
+            "<coverpage><image l:href=\"#{}\"/></coverpage>\n",
+            name
+        ));
+    }
+    fb2.push_str("<lang>en</lang>\n</title-info>\n");
+    fb2.push_str(&format!(
+        "<document-info>
+<author><nickname>lightnovel-cli</nickname></author>
+<program-used>lightnovel-cli</program-used>
+<date>{}</date>
+<src-url>{}</src-url>
+<id>{}</id>
+<version>1.0</version>
+</document-info>
+</description>
+",
+        Local::now().format("%Y-%m-%d"),
+        escape(&book.url),
+        escape(&book.url)
+    ));
+
+    fb2.push_str(&format!(
+        "<body>\n<title><p>{}</p></title>\n",
+        escape(&info.title)
+    ));
+    for chapter in book.chapters.iter() {
+        fb2.push_str(&format!(
+            "<section>\n<title><p>{}</p></title>\n",
+            escape(&chapter_title(chapter))
+        ));
+        for block in chapter_body(chapter) {
+            fb2.push_str(&fb2_block(block));
+        }
+        fb2.push_str("</section>\n");
+    }
+    fb2.push_str("</body>\n");
+
+    if let Some((name, content_type, data)) = &cover {
+        fb2.push_str(&format!(
+            "<binary id=\"{}\" content-type=\"{}\">{}</binary>\n",
+            name,
+            content_type,
+            base64(data)
+        ));
+    }
+    fb2.push_str("</FictionBook>\n");
+    fb2
+}
+
+// "First Middle Last" -> first and last name, a single word is a nickname
+fn author(name: &str) -> String {
+    let words = name.split_whitespace().collect::<Vec<_>>();
+    match words.len() {
+        0 => "<author><nickname>Unknown</nickname></author>\n".to_string(),
+        1 => format!("<author><nickname>{}</nickname></author>\n", escape(name)),
+        _ => format!(
+            "<author><first-name>{}</first-name><last-name>{}</last-name></author>\n",
+            escape(words[0]),
+            escape(&words[1..].join(" "))
+        ),
+    }
+}
+
+fn fb2_block(block: &Block) -> String {
+    match block {
+        Block::Paragraph(spans) => format!("<p>{}</p>\n", fb2_spans(spans)),
+        Block::Heading(spans) => {
+            format!("<subtitle>{}</subtitle>\n", fb2_spans(spans))
+        }
+        Block::Separator => "<subtitle>* * *</subtitle>\n".to_string(),
+        Block::Blockquote(spans) => {
+            format!("<cite><p>{}</p></cite>\n", fb2_spans(spans))
+        }
+        // FictionBook has no lists
+        Block::List(items) => items
+            .iter()
+            .map(|x| format!("<p>• {}</p>\n", fb2_spans(x)))
+            .collect(),
+    }
+}
+
+fn fb2_spans(spans: &[Span]) -> String {
+    let mut fb2 = String::new();
+    for span in spans {
+        let mut text = escape(&span.text);
+        if span.style.italic {
+            text = format!("<emphasis>{}</emphasis>", text);
+        }
+        if span.style.bold {
+            text = format!("<strong>{}</strong>", text);
+        }
+        fb2.push_str(&text);
+    }
+    fb2
+}
+
+// (binary id, content type, data), readers only need to support jpeg and
+// png so anything else is converted to png
+fn cover_image(bytes: &[u8]) -> Option<(&'static str, &'static str, Vec<u8>)> {
+    if bytes.starts_with(&[0xff, 0xd8]) {
+        return Some(("cover.jpg", "image/jpeg", bytes.to_vec()));
+    }
+    if bytes.starts_with(b"\x89PNG") {
+        return Some(("cover.png", "image/png", bytes.to_vec()));
+    }
+    let image = image::load_from_memory(bytes).ok()?;
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .ok()?;
+    Some(("cover.png", "image/png", png.into_inner()))
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for (index, chunk) in data.chunks(3).enumerate() {
+        // keep lines short like other FictionBook writers do
+        if index > 0 && index % 19 == 0 {
+            encoded.push('\n');
+        }
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let triple =
+            (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for position in 0..4 {
+            if position <= chunk.len() {
+                let index = (triple >> (18 - position * 6)) & 0x3f;
+                encoded.push(ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_known_vectors() {
+        // RFC 4648 section 10
+        for (data, encoded) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64(data.as_bytes()), encoded, "{:?}", data);
+        }
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+    }
+
+    #[test]
+    fn base64_lines_are_76_chars() {
+        let encoded = base64(&[0; 100]);
+        let lines = encoded.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 76);
+        assert_eq!(lines[1], format!("{}AA==", "A".repeat(56)));
+    }
+}
@@ -0,0 +1,97 @@
+//! fuzzy matching of titles
+
+/// score how well pattern matches text as a case insensitive subsequence,
+/// None if it doesn't match. consecutive matches and matches at the start of
+/// words score higher, gaps and long texts lower.
+pub fn score(pattern: &str, text: &str) -> Option<i32> {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let mut score = 0;
+    let mut matched = 0;
+    let mut last_match: Option<usize> = None;
+    for (index, c) in text.iter().enumerate() {
+        if matched == pattern.len() {
+            break;
+        }
+        if *c != pattern[matched] {
+            continue;
+        }
+        score += 1;
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 8;
+        }
+        match last_match {
+            Some(last) if last + 1 == index => score += 5,
+            Some(last) => score -= (index - last - 1).min(5) as i32,
+            None => score -= index.min(10) as i32,
+        }
+        last_match = Some(index);
+        matched += 1;
+    }
+
+    if matched < pattern.len() {
+        return None;
+    }
+    Some(score * 10 - text.len() as i32)
+}
+
+/// indices of items matching pattern, best match first
+pub fn rank(pattern: &str, items: &[String]) -> Vec<usize> {
+    let mut scores = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, x)| score(pattern, x).map(|score| (index, score)))
+        .collect::<Vec<_>>();
+    scores.sort_by_key(|x| std::cmp::Reverse(x.1));
+    scores.into_iter().map(|x| x.0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(titles: &[&str]) -> Vec<String> {
+        titles.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn a_prefix_beats_scattered_letters() {
+        let items = titles(&["south china sea", "shadow slave"]);
+        assert_eq!(rank("sha", &items), [1, 0]);
+        assert!(score("sha", "shadow") > score("sha", "sasha"));
+    }
+
+    #[test]
+    fn word_starts_beat_letters_inside_words() {
+        let items = titles(&["unsupervised", "super gene"]);
+        assert_eq!(rank("sup", &items), [1, 0]);
+    }
+
+    #[test]
+    fn case_is_ignored() {
+        assert_eq!(
+            score("SHADOW", "shadow slave"),
+            score("shadow", "Shadow Slave")
+        );
+        assert!(score("ss", "Shadow Slave").is_some());
+    }
+
+    #[test]
+    fn letters_out_of_order_or_missing_dont_match() {
+        assert_eq!(score("wodahs", "shadow"), None);
+        assert_eq!(score("shadowx", "shadow"), None);
+        assert_eq!(
+            rank("zzz", &titles(&["shadow slave"])),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn an_empty_pattern_matches_everything_in_order() {
+        assert_eq!(rank("", &titles(&["b", "a"])), [0, 1]);
+    }
+}
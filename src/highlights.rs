@@ -0,0 +1,374 @@
+//! highlighted paragraphs, or parts of one, and their notes, kept in
+//! `highlights.txt`
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+};
+
+use crate::chapter::Block;
+
+/// a saved highlight, one line of highlights.txt
+#[derive(Clone)]
+pub struct Highlight {
+    /// title of the novel as in the library
+    pub novel: String,
+    /// chapter the highlight is in
+    pub chapter_url: String,
+    /// first paragraph of the highlight
+    pub start: usize,
+    /// last paragraph of the highlight
+    pub end: usize,
+    /// chars of the start paragraph's text when only part of it is
+    /// highlighted, None for whole paragraphs
+    pub span: Option<(usize, usize)>,
+    /// text of the highlighted paragraphs or part
+    pub text: String,
+    /// note typed when saving, may be empty
+    pub note: String,
+}
+
+/// where a highlight is in the blocks of its chapter
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Place {
+    /// first block
+    pub start: usize,
+    /// last block
+    pub end: usize,
+    /// chars of the start block's text, None for whole blocks
+    pub span: Option<(usize, usize)>,
+}
+
+impl Highlight {
+    /// the blocks of the highlight in its chapter, found by its text since
+    /// start and end count the paragraphs the way the chapter was split when
+    /// it was saved, which older versions did differently. The match closest
+    /// to where it was saved wins, None if the text isn't there any more
+    pub fn locate(&self, blocks: &[Block]) -> Option<Place> {
+        if self.span.is_some() {
+            return self.locate_span(blocks);
+        }
+        let text = words(&self.text);
+        if text.is_empty() {
+            return None;
+        }
+        let texts = blocks.iter().map(|x| words(&x.text())).collect::<Vec<_>>();
+        let mut found = Vec::new();
+        for start in 0..texts.len() {
+            if texts[start].is_empty() || !text.starts_with(&texts[start]) {
+                continue;
+            }
+            // take the blocks after it while the text goes on
+            let mut joined = texts[start].clone();
+            let mut end = start;
+            for (index, next) in texts.iter().enumerate().skip(start + 1) {
+                if joined.len() >= text.len() {
+                    break;
+                }
+                if next.is_empty() {
+                    continue;
+                }
+                joined = format!("{} {}", joined, next);
+                if !text.starts_with(&joined) {
+                    break;
+                }
+                end = index;
+            }
+            if joined == text {
+                found.push((start, end));
+            }
+        }
+        // a highlight inside one of the blocks the chapter is split into now
+        if found.is_empty() {
+            found = (0..texts.len())
+                .filter(|x| texts[*x].contains(&text))
+                .map(|x| (x, x))
+                .collect();
+        }
+        found
+            .into_iter()
+            .min_by_key(|(x, _)| x.abs_diff(self.start))
+            .map(|(start, end)| Place {
+                start,
+                end,
+                span: None,
+            })
+    }
+
+    // the text is exactly the chars of the block it was taken from, the
+    // saved block and offset are checked first by being the closest
+    fn locate_span(&self, blocks: &[Block]) -> Option<Place> {
+        let from = self.span.map(|x| x.0).unwrap_or(0);
+        let part = self.text.chars().collect::<Vec<_>>();
+        blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(index, block)| {
+                let text = block.text().chars().collect::<Vec<_>>();
+                positions(&text, &part, |a, b| a == b)
+                    .into_iter()
+                    .map(move |x| (index, x))
+            })
+            .min_by_key(|(index, x)| {
+                (index.abs_diff(self.start), x.abs_diff(from))
+            })
+            .map(|(index, x)| Place {
+                start: index,
+                end: index,
+                span: Some((x, x + part.len())),
+            })
+    }
+}
+
+/// chars of the first place part is in text, ignoring case
+pub fn find_part(text: &str, part: &str) -> Option<(usize, usize)> {
+    let text = text.chars().collect::<Vec<_>>();
+    let part = part.chars().collect::<Vec<_>>();
+    let same = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+    let start = *positions(&text, &part, same).first()?;
+    Some((start, start + part.len()))
+}
+
+// every index of text where part starts
+fn positions(
+    text: &[char],
+    part: &[char],
+    same: impl Fn(char, char) -> bool,
+) -> Vec<usize> {
+    if part.is_empty() || part.len() > text.len() {
+        return vec![];
+    }
+    (0..=text.len() - part.len())
+        .filter(|x| text[*x..].iter().zip(part).all(|(a, b)| same(*a, *b)))
+        .collect()
+}
+
+// the text with every run of whitespace made a single space
+fn words(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// highlights are stored one per line, fields separated by tabs since the
+/// highlighted text and notes can contain the '#' used in novels.txt:
+/// novel, chapter url, start, end, text, note and the span as "from-to",
+/// empty or missing for whole paragraphs
+pub fn load(path: &str) -> Vec<Highlight> {
+    // a missing or unreadable file has nothing in it yet
+    let content = fs::read_to_string(path).unwrap_or_default();
+
+    let mut highlights = Vec::new();
+    for line in content.lines() {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if fields.len() < 6 {
+            continue;
+        }
+        let (start, end) =
+            match (fields[2].parse::<usize>(), fields[3].parse::<usize>()) {
+                (Ok(start), Ok(end)) => (start, end),
+                _ => continue,
+            };
+        highlights.push(Highlight {
+            novel: fields[0].to_string(),
+            chapter_url: fields[1].to_string(),
+            start,
+            end,
+            span: fields.get(6).and_then(|x| parse_span(x)),
+            text: fields[4].to_string(),
+            note: fields[5].to_string(),
+        });
+    }
+    highlights
+}
+
+/// append a highlight to highlights.txt
+pub fn add(path: &str, highlight: &Highlight) -> io::Result<()> {
+    let mut file_buffer =
+        File::options().create(true).append(true).open(path)?;
+
+    writeln!(
+        &mut file_buffer,
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        highlight.novel,
+        highlight.chapter_url,
+        highlight.start,
+        highlight.end,
+        clean_field(&highlight.text),
+        clean_field(&highlight.note),
+        highlight
+            .span
+            .map(|(from, to)| format!("{}-{}", from, to))
+            .unwrap_or_default(),
+    )
+}
+
+fn parse_span(field: &str) -> Option<(usize, usize)> {
+    let (from, to) = field.split_once('-')?;
+    Some((from.parse().ok()?, to.parse().ok()?))
+}
+
+/// write every highlight of a novel to a markdown file, grouped by chapter
+pub fn export_markdown(
+    highlights: &[Highlight],
+    novel: &str,
+    out_path: &str,
+) -> std::io::Result<usize> {
+    let (content, count) = markdown(highlights, novel);
+    let mut file_buffer = File::create(out_path)?;
+    write!(&mut file_buffer, "{}", content)?;
+    Ok(count)
+}
+
+// the markdown export_markdown writes and the number of highlights in it
+fn markdown(highlights: &[Highlight], novel: &str) -> (String, usize) {
+    let mut content = format!("# {}\n", novel);
+    let mut chapters: Vec<&str> = Vec::new();
+    let mut count = 0;
+    for highlight in highlights.iter().filter(|x| x.novel == novel) {
+        if !chapters.contains(&highlight.chapter_url.as_str()) {
+            chapters.push(highlight.chapter_url.as_str());
+        }
+    }
+
+    for chapter in chapters {
+        content.push_str(&format!(
+            "\n## [{}]({})\n",
+            chapter_name(chapter),
+            chapter
+        ));
+        let mut in_chapter = highlights
+            .iter()
+            .filter(|x| x.novel == novel && x.chapter_url == chapter)
+            .collect::<Vec<_>>();
+        in_chapter.sort_by_key(|x| (x.start, x.span.map(|x| x.0)));
+        for highlight in in_chapter {
+            content.push_str(&format!("\n> {}\n", highlight.text));
+            if !highlight.note.is_empty() {
+                content.push_str(&format!("\n{}\n", highlight.note));
+            }
+            count += 1;
+        }
+    }
+    (content, count)
+}
+
+// "https://freewebnovel.com/novel-name/chapter-12.html" -> "chapter 12"
+fn chapter_name(url: &str) -> String {
+    url.rsplit('/')
+        .next()
+        .unwrap_or(url)
+        .trim_end_matches(".html")
+        .replace('-', " ")
+}
+
+fn clean_field(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chapter::{Span, Style};
+
+    fn paragraph(text: &str) -> Block {
+        Block::Paragraph(vec![Span {
+            text: text.to_string(),
+            style: Style::default(),
+        }])
+    }
+
+    fn highlight(
+        text: &str,
+        start: usize,
+        span: Option<(usize, usize)>,
+    ) -> Highlight {
+        Highlight {
+            novel: "novel".to_string(),
+            chapter_url: "https://freewebnovel.com/novel/chapter-1.html"
+                .to_string(),
+            start,
+            end: start,
+            span,
+            text: text.to_string(),
+            note: String::new(),
+        }
+    }
+
+    fn place(start: usize, end: usize, span: Option<(usize, usize)>) -> Place {
+        Place { start, end, span }
+    }
+
+    #[test]
+    fn locate_whole_paragraphs_by_text() {
+        let blocks = [
+            paragraph("One two."),
+            paragraph("Three  four."),
+            paragraph("Five."),
+            paragraph("One two."),
+        ];
+        let locate = |text, start| highlight(text, start, None).locate(&blocks);
+        assert_eq!(locate("Three four. Five.", 0), Some(place(1, 2, None)));
+        // the same text twice, the one closest to where it was saved wins
+        assert_eq!(locate("One two.", 0), Some(place(0, 0, None)));
+        assert_eq!(locate("One two.", 3), Some(place(3, 3, None)));
+        // saved when the paragraph was split differently
+        assert_eq!(locate("four", 9), Some(place(1, 1, None)));
+        assert_eq!(locate("Six.", 0), None);
+        assert_eq!(locate("", 0), None);
+    }
+
+    #[test]
+    fn locate_part_of_a_paragraph() {
+        let blocks = [
+            paragraph("a cat and a cat"),
+            paragraph("no ćat here, a cat"),
+        ];
+        let locate =
+            |start, span| highlight("cat", start, Some(span)).locate(&blocks);
+        // where it was saved
+        assert_eq!(locate(0, (12, 15)), Some(place(0, 0, Some((12, 15)))));
+        // offsets that don't match the text anymore move to the closest match
+        assert_eq!(locate(0, (3, 6)), Some(place(0, 0, Some((2, 5)))));
+        // counted in chars, not bytes
+        assert_eq!(locate(1, (0, 3)), Some(place(1, 1, Some((15, 18)))));
+        assert_eq!(highlight("dog", 0, Some((0, 3))).locate(&blocks), None);
+    }
+
+    #[test]
+    fn find_part_ignores_case() {
+        assert_eq!(find_part("Ærø Island, ærø", "ÆRØ"), Some((0, 3)));
+        assert_eq!(find_part("the Sword", "sword"), Some((4, 9)));
+        assert_eq!(find_part("the Sword", "shield"), None);
+        assert_eq!(find_part("the Sword", ""), None);
+    }
+
+    #[test]
+    fn markdown_groups_by_chapter_in_reading_order() {
+        let chapter = |n: usize| {
+            format!("https://freewebnovel.com/novel/chapter-{}.html", n)
+        };
+        let mut later = highlight("later", 4, None);
+        later.note = "a note".to_string();
+        let mut part = highlight("part", 4, Some((0, 4)));
+        part.chapter_url = chapter(1);
+        let mut second = highlight("second chapter", 0, None);
+        second.chapter_url = chapter(2);
+        let mut other = highlight("other novel", 0, None);
+        other.novel = "other".to_string();
+        let highlights =
+            [later, second, highlight("first", 1, None), part, other];
+
+        let (content, count) = markdown(&highlights, "novel");
+        assert_eq!(count, 4);
+        assert_eq!(
+            content,
+            "# novel\n\
+             \n## [chapter 1](https://freewebnovel.com/novel/chapter-1.html)\n\
+             \n> first\n\
+             \n> later\n\
+             \na note\n\
+             \n> part\n\
+             \n## [chapter 2](https://freewebnovel.com/novel/chapter-2.html)\n\
+             \n> second chapter\n"
+        );
+    }
+}
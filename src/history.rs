@@ -0,0 +1,349 @@
+//! the reading history in `history.txt` and the stats worked out from it
+
+use chrono::{Duration, Local, NaiveDate, TimeZone};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{self, Write},
+};
+
+// longer than this with a chapter open counts as being away from the screen
+const MAX_SESSION_SECONDS: u64 = 2 * 60 * 60;
+
+/// a chapter opened at start and closed at end
+pub struct Record {
+    /// unix time the chapter was opened
+    pub start: u64,
+    /// unix time the chapter was closed
+    pub end: u64,
+    /// title of the novel as in the library
+    pub novel: String,
+    /// chapter that was open
+    pub chapter_url: String,
+    /// words of the chapter that were on screen
+    pub words: usize,
+}
+
+impl Record {
+    /// time spent reading, capped to leave out time away
+    pub fn seconds(&self) -> u64 {
+        self.end.saturating_sub(self.start).min(MAX_SESSION_SECONDS)
+    }
+
+    /// day the chapter was opened, in local time
+    pub fn day(&self) -> NaiveDate {
+        day_of(self.start)
+    }
+}
+
+/// the chapter being read right now
+pub struct Session {
+    /// title of the novel as in the library
+    pub novel: String,
+    /// chapter that is open
+    pub chapter_url: String,
+    /// unix time the chapter was opened
+    pub start: u64,
+    /// words in the whole chapter
+    pub words: usize,
+    /// furthest part of the chapter that was on screen, 0.0 to 1.0
+    pub read: f32,
+}
+
+impl Session {
+    /// the record of the session closed at end
+    pub fn finish(&self, end: u64) -> Record {
+        Record {
+            start: self.start,
+            end,
+            novel: self.novel.clone(),
+            chapter_url: self.chapter_url.clone(),
+            words: (self.words as f32 * self.read) as usize,
+        }
+    }
+}
+
+/// one record per line: start, end, novel, chapter url and words read,
+/// separated by tabs like highlights.txt
+pub fn load(path: &str) -> Vec<Record> {
+    // a missing or unreadable file has nothing in it yet
+    let content = fs::read_to_string(path).unwrap_or_default();
+
+    let mut records = Vec::new();
+    for line in content.lines() {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if fields.len() < 5 {
+            continue;
+        }
+        let (start, end, words) = match (
+            fields[0].parse::<u64>(),
+            fields[1].parse::<u64>(),
+            fields[4].parse::<usize>(),
+        ) {
+            (Ok(start), Ok(end), Ok(words)) => (start, end, words),
+            _ => continue,
+        };
+        records.push(Record {
+            start,
+            end,
+            novel: fields[2].to_string(),
+            chapter_url: fields[3].to_string(),
+            words,
+        });
+    }
+    records
+}
+
+/// append a record to history.txt
+pub fn add(path: &str, record: &Record) -> io::Result<()> {
+    let mut file_buffer =
+        File::options().create(true).append(true).open(path)?;
+
+    writeln!(
+        &mut file_buffer,
+        "{}\t{}\t{}\t{}\t{}",
+        record.start,
+        record.end,
+        record.novel,
+        record.chapter_url,
+        record.words
+    )
+}
+
+/// what was read in some span of time
+#[derive(Default, Clone)]
+pub struct Totals {
+    /// words read
+    pub words: usize,
+    /// time spent reading
+    pub seconds: u64,
+    // a chapter opened twice is still one chapter
+    chapter_urls: BTreeSet<String>,
+}
+
+impl Totals {
+    fn add(&mut self, record: &Record) {
+        self.words += record.words;
+        self.seconds += record.seconds();
+        self.chapter_urls.insert(record.chapter_url.clone());
+    }
+
+    /// different chapters read
+    pub fn chapters(&self) -> usize {
+        self.chapter_urls.len()
+    }
+
+    /// words per minute, 0 if nothing was read
+    pub fn speed(&self) -> usize {
+        if self.seconds < 60 {
+            return 0;
+        }
+        self.words * 60 / self.seconds as usize
+    }
+}
+
+/// the stats screen worked out from the whole history
+pub struct Stats {
+    /// everything ever read
+    pub total: Totals,
+    /// days with any reading, in order
+    pub days: BTreeMap<NaiveDate, Totals>,
+    /// sorted by time spent, most read first
+    pub novels: Vec<(String, Totals)>,
+    /// days in a row with reading, up to today or yesterday
+    pub current_streak: usize,
+    /// most days in a row with reading
+    pub longest_streak: usize,
+}
+
+/// totals, days, novels and streaks of records
+pub fn stats(records: &[Record]) -> Stats {
+    let mut total = Totals::default();
+    let mut days: BTreeMap<NaiveDate, Totals> = BTreeMap::new();
+    let mut novels: BTreeMap<String, Totals> = BTreeMap::new();
+    for record in records {
+        total.add(record);
+        days.entry(record.day()).or_default().add(record);
+        novels.entry(record.novel.clone()).or_default().add(record);
+    }
+
+    // consecutive days with any reading, the current streak may end today
+    // or yesterday
+    let mut longest_streak = 0;
+    let mut streak = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in days.keys() {
+        streak = match previous {
+            Some(previous) if *day - previous == Duration::days(1) => {
+                streak + 1
+            }
+            _ => 1,
+        };
+        longest_streak = longest_streak.max(streak);
+        previous = Some(*day);
+    }
+    let today = Local::now().date_naive();
+    let current_streak = match previous {
+        Some(last) if today - last <= Duration::days(1) => streak,
+        _ => 0,
+    };
+
+    let mut novels = novels.into_iter().collect::<Vec<_>>();
+    novels.sort_by_key(|x| std::cmp::Reverse(x.1.seconds));
+    Stats {
+        total,
+        days,
+        novels,
+        current_streak,
+        longest_streak,
+    }
+}
+
+/// day of a unix time, in local time
+pub fn day_of(timestamp: u64) -> NaiveDate {
+    match Local.timestamp_opt(timestamp as i64, 0).single() {
+        Some(time) => time.date_naive(),
+        None => NaiveDate::default(),
+    }
+}
+
+/// local time like "2024-05-01 21:30:00", empty if out of range
+pub fn format_time(timestamp: u64) -> String {
+    match Local.timestamp_opt(timestamp as i64, 0).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => String::new(),
+    }
+}
+
+/// "2h 05m", "12m"
+pub fn format_duration(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// every record as a csv row, returns the number of rows written
+pub fn export_csv(
+    records: &[Record],
+    out_path: &str,
+) -> std::io::Result<usize> {
+    let mut content = "start,end,novel,chapter_url,words,seconds\n".to_string();
+    for record in records {
+        content.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            format_time(record.start),
+            format_time(record.end),
+            csv_field(&record.novel),
+            csv_field(&record.chapter_url),
+            record.words,
+            record.seconds()
+        ));
+    }
+
+    let mut file_buffer = File::create(out_path)?;
+    write!(&mut file_buffer, "{}", content)?;
+    Ok(records.len())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a record of minutes spent reading chapter at noon days ago
+    fn record(
+        days_ago: i64,
+        chapter: i32,
+        minutes: u64,
+        words: usize,
+    ) -> Record {
+        let day = Local::now().date_naive() - Duration::days(days_ago);
+        let noon = day.and_hms_opt(12, 0, 0).unwrap();
+        let start = Local.from_local_datetime(&noon).earliest().unwrap();
+        let start = start.timestamp() as u64;
+        Record {
+            start,
+            end: start + minutes * 60,
+            novel: "novel".to_string(),
+            chapter_url: format!(
+                "https://example.com/novel/chapter-{}",
+                chapter
+            ),
+            words,
+        }
+    }
+
+    #[test]
+    fn totals_are_counted_per_day() {
+        let records = [
+            record(1, 1, 10, 2000),
+            record(1, 1, 5, 500),
+            record(1, 2, 15, 3000),
+            record(0, 3, 20, 1000),
+        ];
+        let stats = stats(&records);
+        assert_eq!(stats.days.len(), 2);
+        let yesterday = &stats.days[&records[0].day()];
+        assert_eq!(yesterday.words, 5500);
+        assert_eq!(yesterday.seconds, 30 * 60);
+        // chapter 1 was opened twice
+        assert_eq!(yesterday.chapters(), 2);
+        assert_eq!(yesterday.speed(), 183);
+        assert_eq!(stats.total.words, 6500);
+        assert_eq!(stats.total.chapters(), 3);
+    }
+
+    fn stats_of_days(days: &[i64]) -> Stats {
+        let records = days
+            .iter()
+            .map(|x| record(*x, 1, 10, 100))
+            .collect::<Vec<_>>();
+        stats(&records)
+    }
+
+    #[test]
+    fn streaks_are_consecutive_days_with_reading() {
+        // three days in a row, a gap, then yesterday and today
+        let stats = stats_of_days(&[9, 8, 7, 1, 0]);
+        assert_eq!(stats.longest_streak, 3);
+        assert_eq!(stats.current_streak, 2);
+    }
+
+    #[test]
+    fn the_current_streak_can_end_yesterday() {
+        assert_eq!(stats_of_days(&[2, 1]).current_streak, 2);
+        let stats = stats_of_days(&[3, 2]);
+        assert_eq!(stats.current_streak, 0);
+        assert_eq!(stats.longest_streak, 2);
+    }
+
+    #[test]
+    fn no_reading_means_no_streak() {
+        let stats = stats(&[]);
+        assert_eq!(stats.current_streak, 0);
+        assert_eq!(stats.longest_streak, 0);
+        assert_eq!(stats.total.speed(), 0);
+    }
+
+    #[test]
+    fn time_away_from_the_screen_is_capped() {
+        assert_eq!(record(0, 1, 600, 100).seconds(), MAX_SESSION_SECONDS);
+    }
+
+    #[test]
+    fn durations_are_hours_and_minutes() {
+        assert_eq!(format_duration(59), "0m");
+        assert_eq!(format_duration(12 * 60), "12m");
+        assert_eq!(format_duration(2 * 3600 + 5 * 60), "2h 05m");
+    }
+}
@@ -0,0 +1,367 @@
+//! reading the library of other apps: LNReader and Tachiyomi json
+//! backups, csv files and lists of urls
+
+use chrono::{DateTime, NaiveDate};
+use serde_json::Value;
+
+use crate::net;
+use crate::novel;
+use crate::search;
+
+/// a novel read in another app
+pub struct Imported {
+    /// title as shown in the other app
+    pub title: String,
+    /// page of the novel or of a chapter, on any site
+    pub url: String,
+    /// last chapter read, None if not known
+    pub chapter: Option<i32>,
+    /// unix time of the last read, 0 if not known
+    pub last_read: u64,
+}
+
+/// the novels of a LNReader or Tachiyomi json backup, a csv with title and
+/// url columns or a list of urls, whichever content looks like
+pub fn parse(
+    content: &str,
+) -> Result<Vec<Imported>, Box<dyn std::error::Error>> {
+    let trimmed = content.trim_start_matches('\u{feff}').trim();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        return Ok(parse_json(&serde_json::from_str(trimmed)?));
+    }
+    let first = trimmed.lines().next().unwrap_or("");
+    if first.contains(',') && !first.starts_with("http") {
+        return Ok(parse_csv(trimmed));
+    }
+    Ok(parse_urls(trimmed))
+}
+
+fn parse_json(json: &Value) -> Vec<Imported> {
+    // a list of novels, or an object with a list under one of these keys
+    let novels = match json {
+        Value::Array(novels) => novels.clone(),
+        Value::Object(object) => ["mangas", "novels", "library"]
+            .iter()
+            .find_map(|key| object.get(*key).and_then(|x| x.as_array()))
+            .cloned()
+            .unwrap_or_else(|| vec![json.clone()]),
+        _ => vec![],
+    };
+    novels.iter().filter_map(json_novel).collect()
+}
+
+// LNReader novels have name, url or path and a list of chapters marked
+// read/unread, Tachiyomi's have "manga": [url, title, ...] with "chapters"
+// marked "r" and a "history" of read times
+fn json_novel(novel: &Value) -> Option<Imported> {
+    let manga = novel.get("manga");
+    let title = string(novel, &["name", "novelName", "title"])
+        .or_else(|| manga.and_then(|x| string_at(x, 1, "title")))?;
+    let url = string(novel, &["url", "novelUrl", "sourceUrl", "path"])
+        .or_else(|| manga.and_then(|x| string_at(x, 0, "url")))
+        .unwrap_or_default();
+
+    let mut chapter = None;
+    let mut last_read = 0;
+    let chapters = novel.get("chapters").and_then(|x| x.as_array());
+    for item in chapters.into_iter().flatten() {
+        let read = ["read", "r"]
+            .iter()
+            .any(|key| item.get(*key).is_some_and(truthy))
+            || item.get("unread").is_some_and(|x| !truthy(x));
+        if !read {
+            continue;
+        }
+        chapter = chapter.max(chapter_number(item));
+        last_read = last_read.max(time(item, &["readTime", "lastRead"]));
+    }
+    // the furthest chapter read or opened is the one to continue from
+    let history = novel.get("history").and_then(|x| x.as_array());
+    for item in history.into_iter().flatten() {
+        let read_at = time(item, &["r", "lastRead", "readTime"]);
+        last_read = last_read.max(read_at);
+        chapter = chapter.max(chapter_number(item));
+    }
+    Some(Imported {
+        title,
+        url,
+        chapter,
+        last_read,
+    })
+}
+
+// title, url and optionally chapter and last read columns, the header row
+// is skipped
+fn parse_csv(content: &str) -> Vec<Imported> {
+    let mut novels = Vec::new();
+    for line in content.lines() {
+        let fields = csv_fields(line);
+        let title = fields.first().map(|x| x.trim()).unwrap_or("");
+        if title.is_empty() || title.eq_ignore_ascii_case("title") {
+            continue;
+        }
+        let url = fields.get(1).map(|x| x.trim()).unwrap_or("");
+        novels.push(Imported {
+            title: title.to_string(),
+            url: url.to_string(),
+            chapter: fields
+                .get(2)
+                .and_then(|x| x.trim().parse().ok())
+                .or_else(|| novel::chapter_number(url)),
+            last_read: fields
+                .get(3)
+                .map(|x| parse_time(&Value::String(x.to_string())))
+                .unwrap_or(0),
+        });
+    }
+    novels
+}
+
+// one url per line, novel pages or chapters
+fn parse_urls(content: &str) -> Vec<Imported> {
+    content
+        .lines()
+        .map(|x| x.trim())
+        .filter(|x| x.starts_with("http") && x.split('/').count() > 3)
+        .map(|url| Imported {
+            title: slug(url).replace('-', " "),
+            url: url.to_string(),
+            chapter: novel::chapter_number(url),
+            last_read: 0,
+        })
+        .collect()
+}
+
+/// chapter url on the site of the imported novel, the novel is looked up by
+/// title if it was read somewhere else
+pub async fn source_url(
+    novel: &Imported,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let slug = if novel.url.starts_with(&net::site()) {
+        slug(&novel.url)
+    } else {
+        let wanted = normalize(&novel.title);
+        let page = search::search(&novel.title).await?;
+        match page.results.iter().find(|x| normalize(&x.title) == wanted) {
+            Some(result) => slug(&result.url),
+            None => return Err(format!("'{}' not found", novel.title).into()),
+        }
+    };
+    Ok(format!(
+        "{}/{}/chapter-{}.html",
+        net::site(),
+        slug,
+        novel.chapter.unwrap_or(1).max(1)
+    ))
+}
+
+// "https://freewebnovel.com/novel-name.html" -> "novel-name"
+fn slug(url: &str) -> String {
+    url.split('/')
+        .nth(3)
+        .unwrap_or("")
+        .trim_end_matches(".html")
+        .to_string()
+}
+
+// titles compared without case, spaces or punctuation
+fn normalize(title: &str) -> String {
+    title
+        .chars()
+        .filter(|x| x.is_alphanumeric())
+        .flat_map(|x| x.to_lowercase())
+        .collect()
+}
+
+fn string(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| value.get(*key).and_then(|x| x.as_str()))
+        .find(|x| !x.is_empty())
+        .map(|x| x.to_string())
+}
+
+// field of an array like Tachiyomi's manga, or of an object
+fn string_at(value: &Value, index: usize, key: &str) -> Option<String> {
+    value
+        .get(index)
+        .or_else(|| value.get(key))
+        .and_then(|x| x.as_str())
+        .map(|x| x.to_string())
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Bool(value) => *value,
+        Value::Number(value) => value.as_f64().is_some_and(|x| x != 0.0),
+        _ => false,
+    }
+}
+
+// the number of a chapter, its url or its name
+fn chapter_number(chapter: &Value) -> Option<i32> {
+    let number = ["chapterNumber", "number"]
+        .iter()
+        .find_map(|key| chapter.get(*key).and_then(|x| x.as_f64()));
+    if let Some(number) = number {
+        return Some(number as i32);
+    }
+    let url = string(chapter, &["url", "chapterUrl", "path", "u"]);
+    url.and_then(|x| novel::chapter_number(&x)).or_else(|| {
+        string(chapter, &["name", "chapterName"])?
+            .split(|x: char| !x.is_ascii_digit())
+            .find(|x| !x.is_empty())?
+            .parse()
+            .ok()
+    })
+}
+
+fn time(value: &Value, keys: &[&str]) -> u64 {
+    keys.iter()
+        .filter_map(|key| value.get(*key))
+        .map(parse_time)
+        .max()
+        .unwrap_or(0)
+}
+
+// unix seconds or milliseconds, or a date
+fn parse_time(value: &Value) -> u64 {
+    let seconds = match value {
+        Value::Number(number) => number.as_u64().unwrap_or(0),
+        Value::String(text) => {
+            let text = text.trim();
+            if let Ok(number) = text.parse::<u64>() {
+                number
+            } else if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+                time.timestamp().max(0) as u64
+            } else if let Ok(day) = NaiveDate::parse_from_str(
+                text.get(..10).unwrap_or(""),
+                "%Y-%m-%d",
+            ) {
+                day.and_hms_opt(0, 0, 0)
+                    .map(|x| x.and_utc().timestamp().max(0) as u64)
+                    .unwrap_or(0)
+            } else {
+                0
+            }
+        }
+        _ => 0,
+    };
+    // milliseconds since 1970 are past the year 33000 as seconds
+    if seconds > 1_000_000_000_000 {
+        seconds / 1000
+    } else {
+        seconds
+    }
+}
+
+// fields of a csv line, quotes like export_csv writes them
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            ch => fields.last_mut().unwrap().push(ch),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn times_in_milliseconds_and_seconds() {
+        assert_eq!(parse_time(&json!(1_700_000_000)), 1_700_000_000);
+        assert_eq!(parse_time(&json!(1_700_000_000_123u64)), 1_700_000_000);
+        assert_eq!(parse_time(&json!("1700000000123")), 1_700_000_000);
+        assert_eq!(parse_time(&json!("2023-11-14T22:13:20Z")), 1_700_000_000);
+        assert_eq!(parse_time(&json!("1970-01-02")), 86400);
+        assert_eq!(parse_time(&json!("yesterday")), 0);
+    }
+
+    #[test]
+    fn quoted_csv_fields() {
+        assert_eq!(csv_fields("a,b,,c"), ["a", "b", "", "c"]);
+        assert_eq!(
+            csv_fields(r#""Hello, World",url,"say ""hi""""#),
+            ["Hello, World", "url", r#"say "hi""#]
+        );
+    }
+
+    #[test]
+    fn csv_with_a_header() {
+        let novels = parse(
+            "title,url,chapter,last read\n\
+             \"Sword, Shield\",https://x.com/sword,12,2023-11-14T22:13:20Z\n\
+             Other,https://x.com/other/chapter-4.html,,\n",
+        )
+        .unwrap();
+        assert_eq!(novels.len(), 2);
+        assert_eq!(novels[0].title, "Sword, Shield");
+        assert_eq!(novels[0].chapter, Some(12));
+        assert_eq!(novels[0].last_read, 1_700_000_000);
+        // the chapter of the url when the column is empty
+        assert_eq!(novels[1].chapter, Some(4));
+        assert_eq!(novels[1].last_read, 0);
+    }
+
+    #[test]
+    fn lnreader_backup() {
+        let backup = json!({"novels": [{
+            "name": "My Novel",
+            "url": "https://example.com/my-novel",
+            "chapters": [
+                {"chapterNumber": 1, "read": 1, "readTime": "1700000000000"},
+                {"chapterNumber": 2, "unread": 0, "readTime": 1_700_000_100},
+                {"chapterNumber": 3, "unread": 1},
+            ],
+        }]});
+        let novels = parse(&backup.to_string()).unwrap();
+        assert_eq!(novels.len(), 1);
+        assert_eq!(novels[0].title, "My Novel");
+        assert_eq!(novels[0].url, "https://example.com/my-novel");
+        assert_eq!(novels[0].chapter, Some(2));
+        assert_eq!(novels[0].last_read, 1_700_000_100);
+    }
+
+    #[test]
+    fn tachiyomi_backup() {
+        let backup = json!({"mangas": [{
+            "manga": ["/novel/my-novel", "My Novel", 1],
+            "chapters": [
+                {"u": "/novel/my-novel/chapter-1.html", "r": 1},
+                {"u": "/novel/my-novel/chapter-2.html", "r": 0},
+            ],
+            "history": [{
+                "u": "/novel/my-novel/chapter-3.html",
+                "r": 1_700_000_000_000u64,
+            }],
+        }]});
+        let novels = parse(&backup.to_string()).unwrap();
+        assert_eq!(novels.len(), 1);
+        assert_eq!(novels[0].title, "My Novel");
+        assert_eq!(novels[0].url, "/novel/my-novel");
+        assert_eq!(novels[0].chapter, Some(3));
+        assert_eq!(novels[0].last_read, 1_700_000_000);
+    }
+
+    #[test]
+    fn list_of_urls() {
+        let novels = parse(
+            "https://freewebnovel.com/my-novel/chapter-7.html\n\nnot a url\n",
+        )
+        .unwrap();
+        assert_eq!(novels.len(), 1);
+        assert_eq!(novels[0].title, "my novel");
+        assert_eq!(novels[0].chapter, Some(7));
+    }
+}
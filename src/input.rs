@@ -0,0 +1,293 @@
+use crate::ui::*;
+use unicode_width::UnicodeWidthChar;
+
+pub enum Action {
+    Continue,
+    Submit,
+    Cancel,
+}
+
+// single line text input, the text is kept here instead of being read back
+// from the screen
+pub struct LineEditor {
+    buffer: Vec<char>,
+    cursor: usize,
+    // past inputs, oldest first, browsed with up and down
+    history: Vec<String>,
+    history_index: Option<usize>,
+    // text typed before browsing the history
+    draft: String,
+    // candidates for inline completion, in order of preference
+    completions: Vec<String>,
+}
+
+impl LineEditor {
+    pub fn new(text: &str) -> Self {
+        let buffer = text.chars().collect::<Vec<_>>();
+        Self {
+            cursor: buffer.len(),
+            buffer,
+            history: vec![],
+            history_index: None,
+            draft: String::new(),
+            completions: vec![],
+        }
+    }
+
+    pub fn with_history(mut self, history: Vec<String>) -> Self {
+        self.history = history;
+        self
+    }
+
+    pub fn with_completions(mut self, completions: Vec<String>) -> Self {
+        self.completions = completions;
+        self
+    }
+
+    pub fn text(&self) -> String {
+        self.buffer.iter().collect()
+    }
+
+    // edit until enter is pressed, returns None if cancelled with escape
+    pub fn read(&mut self, y: i32, x: i32, width: i32) -> Option<String> {
+        curs_set(true);
+        loop {
+            self.draw(y, x, width);
+            let key = match get_wch() {
+                Some(key) => key,
+                None => continue,
+            };
+            match self.handle_key(key) {
+                Action::Continue => {}
+                Action::Submit => return Some(self.text()),
+                Action::Cancel => return None,
+            }
+        }
+    }
+
+    pub fn handle_key(&mut self, key: WchResult) -> Action {
+        match key {
+            WchResult::Char(ch) => match ch {
+                // enter, pasted text ends at the first newline
+                10 | 13 => return Action::Submit,
+                // esc
+                27 => return Action::Cancel,
+                // backspace
+                8 | 127 => self.backspace(),
+                // ctrl-a and ctrl-e
+                1 => self.cursor = 0,
+                5 => self.cursor = self.buffer.len(),
+                // ctrl-b and ctrl-f
+                2 => self.cursor = self.cursor.saturating_sub(1),
+                6 => self.cursor = (self.cursor + 1).min(self.buffer.len()),
+                // ctrl-d
+                4 => self.delete(),
+                // ctrl-w
+                23 => self.delete_word(),
+                // ctrl-u
+                21 => {
+                    self.buffer.drain(..self.cursor);
+                    self.cursor = 0;
+                }
+                // ctrl-k
+                11 => self.buffer.truncate(self.cursor),
+                // tab
+                9 => self.accept_completion(),
+                _ => match char::from_u32(ch) {
+                    Some(c) if !c.is_control() => self.insert(c),
+                    _ => {}
+                },
+            },
+            WchResult::KeyCode(code) => match code {
+                KEY_ENTER => return Action::Submit,
+                KEY_BACKSPACE => self.backspace(),
+                KEY_DC => self.delete(),
+                KEY_LEFT => self.cursor = self.cursor.saturating_sub(1),
+                KEY_RIGHT => {
+                    if self.cursor == self.buffer.len() {
+                        self.accept_completion();
+                    } else {
+                        self.cursor += 1;
+                    }
+                }
+                KEY_UP => self.browse_history(-1),
+                KEY_DOWN => self.browse_history(1),
+                KEY_HOME => self.cursor = 0,
+                KEY_END => self.cursor = self.buffer.len(),
+                _ => {}
+            },
+        }
+        Action::Continue
+    }
+
+    // draw the text at (y, x), scrolled sideways to keep the cursor visible
+    pub fn draw(&self, y: i32, x: i32, width: i32) {
+        let width = width.max(1) as usize;
+        let mut start = 0;
+        while text_width(&self.buffer[start..self.cursor]) >= width {
+            start += 1;
+        }
+
+        let mut visible = String::new();
+        let mut used = 0;
+        for c in &self.buffer[start..] {
+            let w = c.width().unwrap_or(0);
+            if used + w > width {
+                break;
+            }
+            visible.push(*c);
+            used += w;
+        }
+
+        mvaddstr(y, x, &visible);
+        clrtoeol();
+        // suggest the rest of a completion after the cursor
+        if self.cursor == self.buffer.len() {
+            if let Some(rest) = self.completion() {
+                let rest = rest
+                    .chars()
+                    .scan(used, |used, c| {
+                        *used += c.width().unwrap_or(0);
+                        if *used > width {
+                            None
+                        } else {
+                            Some(c)
+                        }
+                    })
+                    .collect::<String>();
+                attron(A_DIM);
+                addstr(&rest);
+                attroff(A_DIM);
+            }
+        }
+        mv(y, x + text_width(&self.buffer[start..self.cursor]) as i32);
+        refresh();
+    }
+
+    // rest of the first completion starting with the text typed so far
+    fn completion(&self) -> Option<String> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+        // compared char by char, lowercasing can change the length of a text
+        self.completions
+            .iter()
+            .find(|x| {
+                x.chars().count() > self.buffer.len()
+                    && x.chars()
+                        .zip(&self.buffer)
+                        .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+            })
+            .map(|x| x.chars().skip(self.buffer.len()).collect())
+    }
+
+    fn accept_completion(&mut self) {
+        if let Some(rest) = self.completion() {
+            self.buffer.extend(rest.chars());
+            self.cursor = self.buffer.len();
+        }
+    }
+
+    // step through the history, -1 goes back to older entries
+    fn browse_history(&mut self, step: i32) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match (self.history_index, step < 0) {
+            (None, true) => {
+                self.draft = self.text();
+                Some(self.history.len() - 1)
+            }
+            (None, false) => return,
+            (Some(0), true) => Some(0),
+            (Some(index), true) => Some(index - 1),
+            (Some(index), false) if index + 1 < self.history.len() => {
+                Some(index + 1)
+            }
+            // past the newest entry, back to what was being typed
+            (Some(_), false) => None,
+        };
+        self.history_index = index;
+        let text = match index {
+            Some(index) => self.history[index].clone(),
+            None => self.draft.clone(),
+        };
+        self.buffer = text.chars().collect();
+        self.cursor = self.buffer.len();
+    }
+
+    fn insert(&mut self, c: char) {
+        self.buffer.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.buffer.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.buffer.len() {
+            self.buffer.remove(self.cursor);
+        }
+    }
+
+    // delete the word before the cursor and the spaces after it
+    fn delete_word(&mut self) {
+        let mut start = self.cursor;
+        while start > 0 && self.buffer[start - 1] == ' ' {
+            start -= 1;
+        }
+        while start > 0 && self.buffer[start - 1] != ' ' {
+            start -= 1;
+        }
+        self.buffer.drain(start..self.cursor);
+        self.cursor = start;
+    }
+}
+
+fn text_width(chars: &[char]) -> usize {
+    chars.iter().map(|x| x.width().unwrap_or(0)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completion(text: &str, completions: &[&str]) -> Option<String> {
+        LineEditor::new(text)
+            .with_completions(
+                completions.iter().map(|x| x.to_string()).collect(),
+            )
+            .completion()
+    }
+
+    #[test]
+    fn completes_the_first_match_ignoring_case() {
+        let completions = ["Shadow Slave", "shadow slave 2", "Super Gene"];
+        assert_eq!(
+            completion("sha", &completions),
+            Some("dow Slave".to_string())
+        );
+        assert_eq!(
+            completion("SU", &completions),
+            Some("per Gene".to_string())
+        );
+        assert_eq!(completion("x", &completions), None);
+        assert_eq!(completion("", &completions), None);
+    }
+
+    #[test]
+    fn nothing_is_left_to_complete_on_a_full_match() {
+        assert_eq!(completion("super gene", &["Super Gene"]), None);
+    }
+
+    #[test]
+    fn letters_that_lowercase_to_several_chars_count_once() {
+        // 'İ' lowercases to "i̇", one byte longer than it is
+        assert_eq!(completion("İ", &["İz"]), Some("z".to_string()));
+        assert_eq!(completion("İs", &["İstanbul"]), Some("tanbul".to_string()));
+    }
+}
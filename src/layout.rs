@@ -0,0 +1,333 @@
+//! wrapping chapter blocks into styled lines of a given width
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::chapter::{Block, Span, Style};
+
+/// a styled range of chars in a line
+#[derive(Clone, Copy)]
+pub struct Run {
+    /// first char of the run, counted in chars of the line
+    pub start: usize,
+    /// char after the last one
+    pub end: usize,
+    /// style of the chars
+    pub style: Style,
+}
+
+/// a line of a laid out block, ready to draw
+pub struct Line {
+    /// the chars, padding included, ending in a newline
+    pub text: String,
+    /// runs of chars that aren't in the default style
+    pub runs: Vec<Run>,
+}
+
+impl Line {
+    fn blank() -> Self {
+        Line {
+            text: "\n".to_string(),
+            runs: vec![],
+        }
+    }
+}
+
+/// wrap a block to fit the screen width, leaving an eighth of the width as
+/// padding on each side and an empty line after the block, lines longer than
+/// max_width (unless it's 0) are narrowed and centered
+pub fn layout_block(block: &Block, width: i32, max_width: usize) -> Vec<Line> {
+    let mut padding = (width / 8).max(0) as usize;
+    let mut line_len = (width.max(0) as usize).saturating_sub(padding * 2);
+    if max_width > 0 && line_len > max_width {
+        line_len = max_width;
+        padding = (width as usize - line_len) / 2;
+    }
+    let mut lines = Vec::new();
+
+    match block {
+        Block::Paragraph(spans) => wrap(
+            spans,
+            Style::default(),
+            padding,
+            ("", ""),
+            line_len,
+            &mut lines,
+        ),
+        Block::Heading(spans) => {
+            let style = Style {
+                heading: true,
+                ..Style::default()
+            };
+            wrap(spans, style, padding, ("", ""), line_len, &mut lines)
+        }
+        Block::Blockquote(spans) => {
+            let style = Style {
+                quote: true,
+                ..Style::default()
+            };
+            wrap(spans, style, padding, ("│ ", "│ "), line_len, &mut lines)
+        }
+        Block::List(items) => {
+            for item in items {
+                wrap(
+                    item,
+                    Style::default(),
+                    padding,
+                    ("• ", "  "),
+                    line_len,
+                    &mut lines,
+                );
+            }
+        }
+        Block::Separator => {
+            let mark = "* * *";
+            let indent = (width.max(0) as usize).saturating_sub(mark.len()) / 2;
+            lines.push(Line {
+                text: format!("{}{}\n", " ".repeat(indent), mark),
+                runs: vec![],
+            });
+        }
+    }
+    lines.push(Line::blank());
+    lines
+}
+
+/// the block with chars from..to of its text marked as highlighted, list
+/// items count as separated by one char like in Block::text
+pub fn mark_block(block: &Block, from: usize, to: usize) -> Block {
+    match block {
+        Block::Paragraph(spans) => {
+            Block::Paragraph(mark_spans(spans, from, to, 0))
+        }
+        Block::Heading(spans) => Block::Heading(mark_spans(spans, from, to, 0)),
+        Block::Blockquote(spans) => {
+            Block::Blockquote(mark_spans(spans, from, to, 0))
+        }
+        Block::List(items) => {
+            let mut offset = 0;
+            let mut marked = Vec::new();
+            for item in items {
+                marked.push(mark_spans(item, from, to, offset));
+                offset +=
+                    item.iter().map(|x| x.text.chars().count()).sum::<usize>()
+                        + 1;
+            }
+            Block::List(marked)
+        }
+        Block::Separator => Block::Separator,
+    }
+}
+
+// spans split where the marked range starts and ends, offset is the index
+// of the first char of spans in the text the range counts in
+fn mark_spans(
+    spans: &[Span],
+    from: usize,
+    to: usize,
+    mut offset: usize,
+) -> Vec<Span> {
+    let mut marked: Vec<Span> = Vec::new();
+    for span in spans {
+        for c in span.text.chars() {
+            let style = Style {
+                highlight: span.style.highlight
+                    || (offset >= from && offset < to),
+                ..span.style
+            };
+            match marked.last_mut() {
+                Some(last) if last.style == style => last.text.push(c),
+                _ => marked.push(Span {
+                    text: c.to_string(),
+                    style,
+                }),
+            }
+            offset += 1;
+        }
+    }
+    marked
+}
+
+fn wrap(
+    spans: &[Span],
+    base: Style,
+    padding: usize,
+    prefixes: (&str, &str),
+    line_len: usize,
+    lines: &mut Vec<Line>,
+) {
+    // split the styled text into words, a word can change style midway, the
+    // space after a word stays highlighted if it was
+    let mut words: Vec<(Vec<(char, Style)>, Style)> =
+        vec![(vec![], Style::default())];
+    for span in spans {
+        let style = base.merge(span.style);
+        for c in span.text.chars() {
+            if c == ' ' {
+                let (word, space) = words.last_mut().unwrap();
+                if !word.is_empty() {
+                    space.highlight = style.highlight;
+                    words.push((vec![], Style::default()));
+                }
+            } else {
+                words.last_mut().unwrap().0.push((c, style));
+            }
+        }
+    }
+    words.retain(|x| !x.0.is_empty());
+
+    let start_line = |prefix: &str| {
+        let mut line = vec![(' ', Style::default()); padding];
+        line.extend(prefix.chars().map(|x| (x, base)));
+        line
+    };
+
+    // lengths are in columns, wide chars take two
+    let mut line = start_line(prefixes.0);
+    let mut length = width(prefixes.0.chars());
+    let mut has_words = false;
+    for (word, space) in words {
+        let word_len = width(word.iter().map(|x| x.0));
+        length += word_len + 1;
+        if length >= line_len && has_words {
+            lines.push(to_line(&line));
+            line = start_line(prefixes.1);
+            length = width(prefixes.1.chars()) + word_len + 1;
+        }
+        line.extend(word);
+        line.push((' ', space));
+        has_words = true;
+    }
+    lines.push(to_line(&line));
+}
+
+fn width(chars: impl Iterator<Item = char>) -> usize {
+    chars.map(|x| x.width().unwrap_or(0)).sum()
+}
+
+fn to_line(chars: &[(char, Style)]) -> Line {
+    let mut text = chars.iter().map(|x| x.0).collect::<String>();
+    text.push('\n');
+
+    let mut runs: Vec<Run> = Vec::new();
+    for (index, (_, style)) in chars.iter().enumerate() {
+        if *style == Style::default() {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.end == index && run.style == *style => {
+                run.end += 1
+            }
+            _ => runs.push(Run {
+                start: index,
+                end: index + 1,
+                style: *style,
+            }),
+        }
+    }
+    Line { text, runs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, bold: bool) -> Span {
+        Span {
+            text: text.to_string(),
+            style: Style {
+                bold,
+                ..Style::default()
+            },
+        }
+    }
+
+    fn marked(block: &Block) -> Vec<(String, bool)> {
+        let spans = match block {
+            Block::Paragraph(spans) => spans,
+            _ => panic!("not a paragraph"),
+        };
+        spans
+            .iter()
+            .map(|x| (x.text.clone(), x.style.highlight))
+            .collect()
+    }
+
+    #[test]
+    fn mark_block_splits_spans_at_the_range() {
+        let block = Block::Paragraph(vec![
+            span("one two ", false),
+            span("three", true),
+        ]);
+        let block = mark_block(&block, 4, 11);
+        assert_eq!(
+            marked(&block),
+            [
+                ("one ".to_string(), false),
+                ("two ".to_string(), true),
+                ("thr".to_string(), true),
+                ("ee".to_string(), false),
+            ]
+        );
+        // a second range adds to the first
+        let block = mark_block(&block, 0, 1);
+        assert_eq!(marked(&block)[0], ("o".to_string(), true));
+        assert_eq!(marked(&block)[2], ("two ".to_string(), true));
+    }
+
+    #[test]
+    fn mark_block_counts_list_items_like_block_text() {
+        let block =
+            Block::List(vec![vec![span("ab", false)], vec![span("cd", false)]]);
+        assert_eq!(block.text(), "ab\ncd");
+        let Block::List(items) = mark_block(&block, 3, 4) else {
+            panic!("not a list");
+        };
+        assert!(!items[0][0].style.highlight);
+        assert_eq!(items[1][0].text, "c");
+        assert!(items[1][0].style.highlight);
+        assert!(!items[1][1].style.highlight);
+    }
+
+    fn texts(lines: &[Line]) -> Vec<&str> {
+        lines
+            .iter()
+            .map(|x| x.text.trim_end_matches('\n'))
+            .collect()
+    }
+
+    #[test]
+    fn wrap_counts_wide_chars_as_two_columns() {
+        // 3 columns of padding on each side leave 18 for the text
+        let block = Block::Paragraph(vec![span("日本語 日本語 日本語", false)]);
+        let lines = layout_block(&block, 24, 0);
+        let pad = " ".repeat(3);
+        assert_eq!(
+            texts(&lines),
+            [
+                format!("{}日本語 日本語 ", pad),
+                format!("{}日本語 ", pad),
+                String::new(),
+            ]
+        );
+        // the same number of narrow chars fits on one line
+        let block = Block::Paragraph(vec![span("abc abc abc", false)]);
+        assert_eq!(layout_block(&block, 24, 0).len(), 2);
+    }
+
+    #[test]
+    fn highlighted_spaces_between_words_stay_highlighted() {
+        let block = mark_block(
+            &Block::Paragraph(vec![span("one two three", false)]),
+            4,
+            13,
+        );
+        let lines = layout_block(&block, 80, 0);
+        let highlighted = lines[0]
+            .runs
+            .iter()
+            .filter(|x| x.style.highlight)
+            .map(|x| &lines[0].text[x.start..x.end])
+            .collect::<Vec<_>>();
+        assert_eq!(highlighted, ["two three"]);
+    }
+}
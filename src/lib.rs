@@ -0,0 +1,51 @@
+//! Scraping, storage and text layout behind lightnovel-cli, usable without
+//! the terminal interface.
+//!
+//! - [`search`] and [`novel`] find novels on the site, [`chapter`] parses
+//!   their chapters and [`cache`] keeps downloaded ones
+//! - [`library`], [`highlights`], [`history`] and [`search_history`] read
+//!   and write the files in the data directory given by [`paths`]
+//! - [`layout`] wraps chapters to a width, [`export`] and [`fb2`] write them
+//!   to files
+//! - [`net`] sends every request with the settings from [`config`], call
+//!   [`net::configure`] first to use anything but the defaults
+//!
+//! Network and file errors are returned, nothing here panics on a bad page
+//! or url.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use lightnovel_cli::{chapter, novel, search};
+//!
+//! let page = search::search("martial").await?;
+//! let found = page.results.first().ok_or("nothing found")?;
+//! let info = novel::fetch(&found.url).await?;
+//! let url = novel::chapter_url(&info, 1).ok_or("no chapters")?;
+//! let first = chapter::fetch(&url).await?;
+//! println!("{} has {} paragraphs", info.title, first.blocks.len());
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+
+pub mod autoscroll;
+pub mod backup;
+pub mod cache;
+pub mod chapter;
+pub mod config;
+pub mod cover;
+pub mod export;
+pub mod fb2;
+pub mod fuzzy;
+pub mod highlights;
+pub mod history;
+pub mod import;
+pub mod layout;
+pub mod library;
+pub mod net;
+pub mod novel;
+pub mod paths;
+pub mod search;
+pub mod search_history;
+pub mod tts;
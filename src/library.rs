@@ -0,0 +1,499 @@
+//! the novels being read, kept one per line in `novels.txt`
+
+use fs2::FileExt;
+use std::{
+    cmp::Reverse,
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::novel;
+
+/// where a novel is in the reading list
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// being read, the default
+    Reading,
+    /// to read later
+    PlanToRead,
+    /// paused for now
+    OnHold,
+    /// read to the end
+    Completed,
+    /// stopped reading
+    Dropped,
+}
+
+impl Status {
+    /// every status, in the order they are cycled through
+    pub const ALL: [Status; 5] = [
+        Status::Reading,
+        Status::PlanToRead,
+        Status::OnHold,
+        Status::Completed,
+        Status::Dropped,
+    ];
+
+    /// name stored in novels.txt
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Reading => "reading",
+            Status::PlanToRead => "plan-to-read",
+            Status::OnHold => "on-hold",
+            Status::Completed => "completed",
+            Status::Dropped => "dropped",
+        }
+    }
+
+    /// name shown on screen
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Reading => "Reading",
+            Status::PlanToRead => "Plan to read",
+            Status::OnHold => "On hold",
+            Status::Completed => "Completed",
+            Status::Dropped => "Dropped",
+        }
+    }
+
+    /// entries written before statuses existed are being read
+    pub fn parse(text: &str) -> Status {
+        Status::ALL
+            .into_iter()
+            .find(|x| x.as_str() == text.trim())
+            .unwrap_or(Status::Reading)
+    }
+
+    /// the status after this one, cycling back to the first
+    pub fn next(self) -> Status {
+        let index = Status::ALL.iter().position(|x| *x == self).unwrap();
+        Status::ALL[(index + 1) % Status::ALL.len()]
+    }
+}
+
+/// orders the library screen can show novels in
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    /// order of novels.txt, most recently read first
+    LastRead,
+    /// by title, a to z
+    Title,
+    /// most unread chapters first
+    Unread,
+    /// most recently added first
+    Added,
+    /// furthest read first, unknown progress last
+    Progress,
+}
+
+impl Sort {
+    /// every order, in the order they are cycled through
+    pub const ALL: [Sort; 5] = [
+        Sort::LastRead,
+        Sort::Title,
+        Sort::Unread,
+        Sort::Added,
+        Sort::Progress,
+    ];
+
+    /// name stored in config.toml
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Sort::LastRead => "last-read",
+            Sort::Title => "title",
+            Sort::Unread => "unread",
+            Sort::Added => "added",
+            Sort::Progress => "progress",
+        }
+    }
+
+    /// name shown on screen
+    pub fn label(self) -> &'static str {
+        match self {
+            Sort::LastRead => "last read",
+            Sort::Title => "title",
+            Sort::Unread => "unread chapters",
+            Sort::Added => "date added",
+            Sort::Progress => "progress",
+        }
+    }
+
+    /// the order named in config.toml, last read if unknown
+    pub fn parse(text: &str) -> Sort {
+        Sort::ALL
+            .into_iter()
+            .find(|x| x.as_str() == text.trim())
+            .unwrap_or(Sort::LastRead)
+    }
+
+    /// the order after this one, cycling back to the first
+    pub fn next(self) -> Sort {
+        let index = Sort::ALL.iter().position(|x| *x == self).unwrap();
+        Sort::ALL[(index + 1) % Sort::ALL.len()]
+    }
+}
+
+/// a novel in the library, one line of novels.txt
+#[derive(Clone)]
+pub struct Entry {
+    /// made from the url, the key novels are matched by
+    pub title: String,
+    /// chapter to continue from
+    pub url: String,
+    /// where the novel is in the reading list
+    pub status: Status,
+    /// tags given on the library screen, without the #
+    pub tags: Vec<String>,
+    /// when the novel was added, a unix timestamp, 0 if not known
+    pub added: u64,
+    /// when the novel was last read, a unix timestamp, 0 if never
+    pub last_read: u64,
+    /// chapters the novel had when we last looked, 0 if not known
+    pub chapters: i32,
+    /// chapters the novel had when we last read it, more than that are new
+    pub seen: i32,
+    /// title from the novel page, empty until its details were opened
+    pub name: String,
+}
+
+impl Entry {
+    /// a novel added now, being read from url
+    pub fn new(title: &str, url: &str) -> Self {
+        Entry {
+            title: title.to_string(),
+            url: url.to_string(),
+            status: Status::Reading,
+            tags: vec![],
+            added: now(),
+            last_read: 0,
+            chapters: 0,
+            seen: 0,
+            name: String::new(),
+        }
+    }
+
+    /// real title if we know it, otherwise the one made from the url
+    pub fn display_title(&self) -> &str {
+        if self.name.is_empty() {
+            &self.title
+        } else {
+            &self.name
+        }
+    }
+
+    /// chapters released since we last read the novel
+    pub fn new_chapters(&self) -> i32 {
+        if self.seen > 0 {
+            (self.chapters - self.seen).max(0)
+        } else {
+            0
+        }
+    }
+
+    /// take the chapter count and name from the landing page of the novel
+    pub fn refresh(&mut self, info: &novel::NovelInfo) {
+        self.chapters = info.chapters;
+        if !info.title.is_empty() {
+            self.name = info.title.clone();
+        }
+        // novels added before chapters were tracked
+        if self.seen == 0 {
+            self.seen = info.chapters;
+        }
+    }
+
+    /// number of the chapter to continue from, 0 if not known
+    pub fn chapter(&self) -> i32 {
+        novel::chapter_number(&self.url).unwrap_or(0)
+    }
+
+    /// chapters after the one to continue from, 0 if not known
+    pub fn unread(&self) -> i32 {
+        (self.chapters - self.chapter()).max(0)
+    }
+
+    /// percentage of chapters read, None if the chapter count isn't known
+    pub fn progress(&self) -> Option<i32> {
+        if self.chapters > 0 {
+            Some((self.chapter() * 100 / self.chapters).min(100))
+        } else {
+            None
+        }
+    }
+}
+
+/// unix time now, 0 if the clock is before 1970
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0)
+}
+
+/// novels.txt has one novel per line, most recently read first:
+/// title#url#status#tag1,tag2#added#last_read#chapters#seen#name
+/// older files only have the title and url, a missing file is an empty
+/// library
+pub fn load(path: &str) -> io::Result<Vec<Entry>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(parse(&content)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err),
+    }
+}
+
+/// entries of the lines of novels.txt, broken lines are skipped
+pub fn parse(content: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    for line in content.lines() {
+        let fields = line.split('#').collect::<Vec<_>>();
+        if fields.len() < 2 || fields[0].trim().is_empty() {
+            continue;
+        }
+        entries.push(Entry {
+            title: fields[0].trim().to_string(),
+            url: fields[1].trim().to_string(),
+            status: Status::parse(fields.get(2).unwrap_or(&"")),
+            tags: parse_tags(fields.get(3).unwrap_or(&"")),
+            added: number(fields.get(4)) as u64,
+            last_read: number(fields.get(5)) as u64,
+            chapters: number(fields.get(6)) as i32,
+            seen: number(fields.get(7)) as i32,
+            name: fields.get(8).unwrap_or(&"").trim().to_string(),
+        });
+    }
+    entries
+}
+
+// write the library to a temporary file and rename it over novels.txt so a
+// crash can't leave half a library behind
+fn save(path: &str, entries: &[Entry]) -> io::Result<()> {
+    let content = entries
+        .iter()
+        .map(|x| {
+            format!(
+                "{}#{}#{}#{}#{}#{}#{}#{}#{}",
+                x.title,
+                x.url,
+                x.status.as_str(),
+                x.tags.join(","),
+                x.added,
+                x.last_read,
+                x.chapters,
+                x.seen,
+                x.name.replace('#', "")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let temp_path = format!("{}.tmp", path);
+    let mut file_buffer = File::create(&temp_path)?;
+    write!(&mut file_buffer, "{}", content)?;
+    file_buffer.sync_all()?;
+    fs::rename(&temp_path, path)
+}
+
+/// change the library as it is on disk right now, other instances wait for
+/// each other so none of them writes over what another just saved. nothing
+/// is written if the library can't be read
+pub fn modify<F>(path: &str, change: F) -> io::Result<()>
+where
+    F: FnOnce(&mut Vec<Entry>) -> bool,
+{
+    // the lock is on a file of its own, novels.txt is replaced on every save
+    let lock = File::create(Path::new(path).with_extension("lock"))?;
+    lock.lock_exclusive()?;
+    let mut entries = load(path)?;
+    if change(&mut entries) {
+        save(path, &entries)?;
+    }
+    // dropping the file releases the lock
+    Ok(())
+}
+
+/// entries keep the order of novels.txt for Sort::LastRead
+pub fn sort(entries: &mut [Entry], sort: Sort) {
+    match sort {
+        Sort::LastRead => {}
+        Sort::Title => entries.sort_by_key(|x| x.title.to_lowercase()),
+        Sort::Unread => entries.sort_by_key(|x| Reverse(x.unread())),
+        Sort::Added => entries.sort_by_key(|x| Reverse(x.added)),
+        Sort::Progress => {
+            entries.sort_by_key(|x| Reverse(x.progress().unwrap_or(-1)))
+        }
+    }
+}
+
+/// add the novels of other to entries, a novel in both keeps the progress of
+/// the one read last
+pub fn merge(entries: &mut Vec<Entry>, other: Vec<Entry>) {
+    for entry in other {
+        match entries.iter_mut().find(|x| x.title == entry.title) {
+            Some(existing) => {
+                let added = match (existing.added, entry.added) {
+                    (0, added) | (added, 0) => added,
+                    (a, b) => a.min(b),
+                };
+                let mut tags = existing.tags.clone();
+                for tag in entry.tags.iter() {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                }
+                let chapters = existing.chapters.max(entry.chapters);
+                if is_newer(&entry, existing) {
+                    *existing = entry;
+                }
+                existing.added = added;
+                existing.tags = tags;
+                existing.chapters = chapters;
+            }
+            None => entries.push(entry),
+        }
+    }
+}
+
+// read more recently, or further if we can't tell when
+fn is_newer(entry: &Entry, other: &Entry) -> bool {
+    if entry.last_read != other.last_read {
+        entry.last_read > other.last_read
+    } else {
+        entry.chapter() > other.chapter()
+    }
+}
+
+/// move the novel of url to the top of the library, adding it if new
+pub fn add(path: &str, url: &str, last_read: u64) -> io::Result<()> {
+    let title = title_from_url(url).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("not a novel url: {}", url),
+        )
+    })?;
+    modify(path, |entries| {
+        let mut entry = match entries.iter().position(|x| x.title == title) {
+            Some(index) => entries.remove(index),
+            None => Entry::new(&title, url),
+        };
+        entry.url = url.trim().to_string();
+        entry.last_read = last_read;
+        entry.seen = entry.chapters;
+        // push most recent novel to top of list
+        entries.insert(0, entry);
+        true
+    })
+}
+
+/// change the entry of a novel in place, keeping the order of the library
+pub fn update<F>(path: &str, title: &str, change: F) -> io::Result<()>
+where
+    F: FnOnce(&mut Entry),
+{
+    modify(path, |entries| {
+        match entries.iter_mut().find(|x| x.title == title) {
+            Some(entry) => {
+                change(entry);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// "a, b ,,c" -> ["a", "b", "c"], '#' would break the line format
+pub fn parse_tags(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|x| x.replace('#', "").trim().to_string())
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// `"https://freewebnovel.com/novel-name/chapter-1.html"` -> "novel name",
+/// None if url has no path
+pub fn title_from_url(url: &str) -> Option<String> {
+    let name = url.split('/').nth(3).filter(|x| !x.is_empty())?;
+    Some(name.replace('-', " "))
+}
+
+fn number(field: Option<&&str>) -> i64 {
+    field.and_then(|x| x.trim().parse().ok()).unwrap_or(0)
+}
+
+/// "just now", "5m ago", "3h ago", "2d ago", "4mo ago", "1y ago"
+pub fn relative_time(then: u64) -> String {
+    if then == 0 {
+        return "never".to_string();
+    }
+    let seconds = now().saturating_sub(then);
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        86400..=2591999 => format!("{}d ago", seconds / 86400),
+        2592000..=31535999 => format!("{}mo ago", seconds / 2592000),
+        _ => format!("{}y ago", seconds / 31536000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(chapter: i32, last_read: u64, added: u64, tags: &[&str]) -> Entry {
+        let url =
+            format!("https://freewebnovel.com/novel/chapter-{}.html", chapter);
+        Entry {
+            added,
+            last_read,
+            tags: tags.iter().map(|x| x.to_string()).collect(),
+            ..Entry::new("novel", &url)
+        }
+    }
+
+    #[test]
+    fn merge_keeps_the_progress_read_last() {
+        let mut entries = vec![entry(40, 200, 50, &["a"])];
+        merge(&mut entries, vec![entry(10, 300, 20, &["b", "a"])]);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].chapter(), 10);
+        assert_eq!(entries[0].last_read, 300);
+        assert_eq!(entries[0].added, 20);
+        assert_eq!(entries[0].tags, ["a", "b"]);
+
+        // an older copy doesn't move the progress back
+        merge(&mut entries, vec![entry(90, 100, 0, &[])]);
+        assert_eq!(entries[0].chapter(), 10);
+        assert_eq!(entries[0].added, 20);
+    }
+
+    #[test]
+    fn merge_takes_the_further_chapter_when_read_at_the_same_time() {
+        let mut entries = vec![entry(5, 0, 0, &[])];
+        merge(&mut entries, vec![entry(7, 0, 0, &[])]);
+        assert_eq!(entries[0].chapter(), 7);
+        merge(&mut entries, vec![entry(6, 0, 0, &[])]);
+        assert_eq!(entries[0].chapter(), 7);
+    }
+
+    #[test]
+    fn merge_adds_novels_not_in_the_library() {
+        let mut entries = vec![entry(1, 0, 0, &[])];
+        let other = Entry::new("other", "https://freewebnovel.com/other");
+        merge(&mut entries, vec![other]);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].title, "other");
+    }
+
+    #[test]
+    fn title_from_url_needs_a_novel() {
+        assert_eq!(
+            title_from_url("https://freewebnovel.com/my-novel/chapter-3.html"),
+            Some("my novel".to_string())
+        );
+        assert_eq!(title_from_url("https://freewebnovel.com/"), None);
+        assert_eq!(title_from_url("not a url"), None);
+    }
+}
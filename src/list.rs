@@ -0,0 +1,107 @@
+use crate::ui::*;
+use unicode_width::UnicodeWidthChar;
+
+// a scrollable list with a highlighted selection bar, the selected item is
+// taken from here instead of read back from the screen
+pub struct ListView<T> {
+    pub items: Vec<T>,
+    pub selected: usize,
+    // index of the first item on screen
+    pub offset: usize,
+}
+
+impl<T> ListView<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        Self {
+            items,
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn selected_item(&self) -> Option<&T> {
+        self.items.get(self.selected)
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+    }
+
+    // move the selection for j/k, arrows, page up/down, g and G, returns
+    // false if the key isn't a movement key
+    pub fn handle_key(&mut self, ch: i32, height: i32) -> bool {
+        let page = (height / 2).max(1) as usize;
+        match ch {
+            // j or down_arrow
+            106 | KEY_DOWN => self.select(self.selected + 1),
+            // k or up_arrow
+            107 | KEY_UP => self.select(self.selected.saturating_sub(1)),
+            // d or page down
+            100 | KEY_NPAGE => self.select(self.selected + page),
+            // u or page up
+            117 | KEY_PPAGE => self.select(self.selected.saturating_sub(page)),
+            // g or home
+            103 | KEY_HOME => self.select(0),
+            // G or end
+            71 | KEY_END => self.select(self.items.len().saturating_sub(1)),
+            _ => return false,
+        }
+        true
+    }
+
+    // draw the items on rows y..y + height, label gives the text of an item
+    // for the available width
+    pub fn draw<F>(&mut self, y: i32, height: i32, width: i32, label: F)
+    where
+        F: Fn(&T, usize) -> String,
+    {
+        let height = height.max(1) as usize;
+        let width = width.max(1) as usize;
+        // scroll to keep the selection on screen
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+
+        for (row, index) in
+            (self.offset..self.items.len()).take(height).enumerate()
+        {
+            let text = fit(&label(&self.items[index], width), width);
+            let y = y + row as i32;
+            mvaddstr(y, 0, &text);
+            clrtoeol();
+            if index == self.selected {
+                mvchgat(y, 0, -1, A_REVERSE, 0);
+            }
+        }
+    }
+}
+
+// cut text to width columns, ending with "..." if it didn't fit
+pub fn fit(text: &str, width: usize) -> String {
+    let total: usize = text.chars().map(|x| x.width().unwrap_or(0)).sum();
+    if total <= width {
+        return text.to_string();
+    }
+    let mut result = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 3 > width {
+            break;
+        }
+        result.push(c);
+        used += w;
+    }
+    result.push_str("...");
+    result
+}
+
+// fit text to width columns and fill the rest with spaces, for columns
+pub fn pad(text: &str, width: usize) -> String {
+    let mut text = fit(text, width);
+    let used: usize = text.chars().map(|x| x.width().unwrap_or(0)).sum();
+    text.push_str(&" ".repeat(width.saturating_sub(used)));
+    text
+}
@@ -1,48 +1,70 @@
+mod cli;
+mod input;
+mod list;
 mod screen;
+mod ui;
 
+use lightnovel_cli::{
+    config::{self, Config},
+    net,
+    paths::Paths,
+};
 use screen::Screen;
 use std::env;
-use std::fs;
-use std::path::Path;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // create config directory if it doesnt exist
-    let path = create_config_dir();
-
-    Screen::new(path).await?;
+    let mut args = env::args().collect::<Vec<_>>();
+    // --config-dir can come before or after the command
+    let mut dir = None;
+    if let Some(index) = args.iter().position(|x| x == "--config-dir") {
+        if index + 1 >= args.len() {
+            eprintln!("--config-dir needs a value\n{}", cli::USAGE);
+            std::process::exit(1);
+        }
+        dir = Some(args.remove(index + 1));
+        args.remove(index);
+    }
+    if let Some("help" | "-h" | "--help") = args.get(1).map(|x| x.as_str()) {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    let paths = match Paths::new(dir.as_deref()) {
+        Ok(paths) => paths,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    for (from, to) in &paths.unmoved {
+        eprintln!(
+            "couldn't move {} to {}, move it yourself to keep it",
+            from.display(),
+            to.display()
+        );
+    }
 
-    Ok(())
-}
+    let config = config::load(&paths.config()).unwrap_or_else(|err| {
+        eprintln!("{}, using the defaults", err);
+        Config::default()
+    });
+    if let Err(err) = net::configure(&config) {
+        eprintln!("network settings in config.toml: {}", err);
+    }
 
-pub fn create_config_dir() -> String {
-    // create config dir if doesnt exist already
-    // fs::create_dir_all("/some/dir")?;
-    let mut path: String;
-    match env::var("XDG_CONFIG_HOME") {
-        Ok(v) => {
-            path = v.to_string();
-            path.push_str("/lightnovel-cli");
-            fs::create_dir_all(path.as_str()).unwrap();
-            path.push_str("/novels.txt");
-            if Path::new(path.as_str()).exists() {
-                ()
-            } else {
-                fs::File::create(&path).unwrap();
-            }
+    match args.get(1).map(|x| x.as_str()) {
+        None => {
+            Screen::new(paths).await?;
         }
-        Err(_) => {
-            path = env::var("HOME").unwrap().to_string();
-            path.push_str("/.config/lightnovel-cli");
-            fs::create_dir_all(path.as_str()).unwrap();
-            path.push_str("/novels.txt");
-            if Path::new(path.as_str()).exists() {
-                ()
-            } else {
-                fs::File::create(&path).unwrap();
-            }
+        Some("export") => cli::export(&args[2..], &paths).await?,
+        Some("backup") => cli::backup(&args[2..], &paths)?,
+        Some("restore") => cli::restore(&args[2..], &paths)?,
+        Some("import") => cli::import(&args[2..], &paths).await?,
+        Some(command) => {
+            eprintln!("unknown command '{}'\n{}", command, cli::USAGE);
+            std::process::exit(1);
         }
-    };
+    }
 
-    path
+    Ok(())
 }
@@ -0,0 +1,93 @@
+//! http requests with the source and network settings of the config
+
+use reqwest::{Client, RequestBuilder, Response};
+use std::{sync::RwLock, time::Duration};
+
+use crate::config::Config;
+
+/// site used when the config doesn't name one
+pub const DEFAULT_SITE: &str = "https://freewebnovel.com";
+
+struct Settings {
+    site: String,
+    client: Option<Client>,
+    retries: u32,
+}
+
+// set once from config.toml and again whenever the settings screen changes
+// it, every request goes through here
+static SETTINGS: RwLock<Settings> = RwLock::new(Settings {
+    site: String::new(),
+    client: None,
+    retries: 0,
+});
+
+/// use the source and network settings of config from now on
+pub fn configure(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let network = &config.network;
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(network.timeout_secs.max(1)));
+    if !network.user_agent.is_empty() {
+        builder = builder.user_agent(network.user_agent.clone());
+    }
+    if !network.proxy.is_empty() {
+        builder = builder.proxy(reqwest::Proxy::all(&network.proxy)?);
+    }
+    let client = builder.build()?;
+    let mut settings = SETTINGS.write().unwrap();
+    settings.site = config.source.site.trim().trim_end_matches('/').to_string();
+    settings.client = Some(client);
+    settings.retries = network.retries;
+    Ok(())
+}
+
+/// `"https://freewebnovel.com"`, without a trailing slash
+pub fn site() -> String {
+    let settings = SETTINGS.read().unwrap();
+    if settings.site.is_empty() {
+        DEFAULT_SITE.to_string()
+    } else {
+        settings.site.clone()
+    }
+}
+
+/// get url
+pub async fn get(url: &str) -> reqwest::Result<Response> {
+    send(|client| client.get(url)).await
+}
+
+/// post form to url
+pub async fn post_form(
+    url: &str,
+    form: &[(&str, &str)],
+) -> reqwest::Result<Response> {
+    send(|client| client.post(url).form(form)).await
+}
+
+// requests that couldn't connect or timed out are tried again
+async fn send<F>(request: F) -> reqwest::Result<Response>
+where
+    F: Fn(&Client) -> RequestBuilder,
+{
+    let (client, retries) = {
+        let settings = SETTINGS.read().unwrap();
+        (
+            settings.client.clone().unwrap_or_default(),
+            settings.retries,
+        )
+    };
+    let mut attempt = 0;
+    loop {
+        match request(&client).send().await {
+            Err(err)
+                if attempt < retries
+                    && (err.is_connect() || err.is_timeout()) =>
+            {
+                attempt += 1;
+                tokio::time::sleep(Duration::from_millis(500 * attempt as u64))
+                    .await;
+            }
+            result => return result,
+        }
+    }
+}
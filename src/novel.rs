@@ -0,0 +1,169 @@
+//! novel landing pages and chapter urls
+
+use scraper::{Html, Selector};
+
+use crate::net;
+use crate::search::{absolute_url, element_text};
+
+/// what the landing page of a novel says about it
+pub struct NovelInfo {
+    /// the title as the site writes it
+    pub title: String,
+    /// link to the cover image, may be empty
+    pub cover_url: String,
+    /// who wrote it
+    pub author: String,
+    /// genres as listed
+    pub genres: Vec<String>,
+    /// "Ongoing", "Completed" or what the site calls it
+    pub status: String,
+    /// chapters released so far, 0 if not known
+    pub chapters: i32,
+    /// link to the latest chapter, used to work out other chapter urls
+    pub latest_url: String,
+    /// the description
+    pub synopsis: String,
+}
+
+/// download and parse the landing page of a novel
+pub async fn fetch(url: &str) -> Result<NovelInfo, Box<dyn std::error::Error>> {
+    let resp = net::get(url).await?.text().await?;
+    Ok(parse(&resp))
+}
+
+/// what a landing page says, fields the page lacks are left empty
+pub fn parse(html: &str) -> NovelInfo {
+    let fragment = Html::parse_fragment(html);
+    // the og:novel meta tags are the most stable part of the page, the
+    // visible info box is only used if they are missing
+    let meta = |property: &str| {
+        let selector =
+            Selector::parse(&format!(r#"meta[property="{}"]"#, property))
+                .unwrap();
+        fragment
+            .select(&selector)
+            .next()
+            .and_then(|x| x.value().attr("content"))
+            .map(|x| x.trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut info = NovelInfo {
+        title: meta("og:novel:novel_name"),
+        cover_url: meta("og:image"),
+        author: meta("og:novel:author"),
+        genres: meta("og:novel:genre")
+            .split(',')
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .collect(),
+        status: meta("og:novel:status"),
+        chapters: 0,
+        latest_url: meta("og:novel:latest_chapter_url"),
+        synopsis: String::new(),
+    };
+
+    if info.title.is_empty() {
+        let selector = Selector::parse("h1").unwrap();
+        if let Some(title) = fragment.select(&selector).next() {
+            info.title = element_text(title);
+        }
+    }
+    if info.cover_url.is_empty() {
+        let selector = Selector::parse("div.pic img").unwrap();
+        if let Some(img) = fragment.select(&selector).next() {
+            info.cover_url = img.value().attr("src").unwrap_or("").to_string();
+        }
+    }
+
+    let item_selector = Selector::parse(r#"div[class="item"]"#).unwrap();
+    let label_selector = Selector::parse("span[title]").unwrap();
+    let right_selector = Selector::parse(r#"div[class="right"]"#).unwrap();
+    let link_selector = Selector::parse("a").unwrap();
+    for item in fragment.select(&item_selector) {
+        let label = match item.select(&label_selector).next() {
+            Some(x) => x.value().attr("title").unwrap_or(""),
+            None => continue,
+        };
+        let right = match item.select(&right_selector).next() {
+            Some(x) => x,
+            None => continue,
+        };
+        match label {
+            "Author" if info.author.is_empty() => {
+                info.author = element_text(right)
+            }
+            "Genre" if info.genres.is_empty() => {
+                info.genres =
+                    right.select(&link_selector).map(element_text).collect()
+            }
+            "Status" if info.status.is_empty() => {
+                info.status = element_text(right)
+            }
+            _ => {}
+        }
+    }
+
+    if info.latest_url.is_empty() {
+        let selector = Selector::parse("ul.ul-list5 a").unwrap();
+        if let Some(link) = fragment.select(&selector).next() {
+            info.latest_url = link.value().attr("href").unwrap_or("").into();
+        }
+    }
+    if !info.latest_url.is_empty() {
+        info.latest_url = absolute_url(&info.latest_url);
+        info.chapters = chapter_number(&info.latest_url).unwrap_or(0);
+    }
+    if !info.cover_url.is_empty() {
+        info.cover_url = absolute_url(&info.cover_url);
+    }
+
+    let selector = Selector::parse("div.m-desc div.inner p").unwrap();
+    let mut paragraphs = fragment
+        .select(&selector)
+        .map(element_text)
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>();
+    if paragraphs.is_empty() {
+        // some novels have the synopsis as loose text
+        let selector = Selector::parse("div.m-desc div.inner").unwrap();
+        paragraphs.extend(fragment.select(&selector).map(element_text));
+    }
+    info.synopsis = paragraphs.join("\n");
+    info
+}
+
+/// landing page of the novel a chapter belongs to,
+/// `"https://freewebnovel.com/novel-name/chapter-12.html"` ->
+/// `"https://freewebnovel.com/novel-name.html"`
+pub fn url_from_chapter(chapter_url: &str) -> String {
+    let slug = chapter_url.split('/').nth(3).unwrap_or("");
+    format!("{}/{}.html", net::site(), slug)
+}
+
+/// `"https://freewebnovel.com/novel-name/chapter-12.html"` -> 12
+pub fn chapter_number(chapter_url: &str) -> Option<i32> {
+    chapter_url
+        .rsplit('/')
+        .next()?
+        .trim_end_matches(".html")
+        .rsplit('-')
+        .next()?
+        .parse::<i32>()
+        .ok()
+}
+
+/// url of chapter number of the novel, worked out from the latest chapter
+pub fn chapter_url(info: &NovelInfo, number: i32) -> Option<String> {
+    offset_chapter_url(&info.latest_url, number - info.chapters)
+}
+
+/// url of the chapter offset chapters after the one of url, None if url
+/// isn't a chapter
+pub fn offset_chapter_url(url: &str, offset: i32) -> Option<String> {
+    // ".../novel-name/chapter-12.html" -> ".../novel-name", "chapter", "12"
+    let (novel, page) = url.rsplit_once('/')?;
+    let (name, number) = page.trim_end_matches(".html").rsplit_once('-')?;
+    let number = number.parse::<i32>().ok()? + offset;
+    Some(format!("{}/{}-{}.html", novel, name, number))
+}
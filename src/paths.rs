@@ -0,0 +1,155 @@
+//! where settings, the library and downloaded chapters are kept
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const APP: &str = "lightnovel-cli";
+// puts everything in one directory like --config-dir does
+const DIR_VAR: &str = "LIGHTNOVEL_CLI_DIR";
+
+/// files the library is made of, kept in the data directory
+pub const DATA_FILES: [&str; 4] = [
+    "novels.txt",
+    "highlights.txt",
+    "history.txt",
+    "search_history.txt",
+];
+
+/// settings go in the config directory, the library, highlights and history
+/// in the data directory and downloaded chapters in the cache directory
+pub struct Paths {
+    /// where config.toml is
+    pub config_dir: PathBuf,
+    /// where the library, highlights and history are
+    pub data_dir: PathBuf,
+    /// where downloaded chapters are
+    pub cache_dir: PathBuf,
+    /// files an older version left in the config directory that couldn't be
+    /// moved to their new place, as (from, to)
+    pub unmoved: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Paths {
+    /// the directories of --config-dir or $LIGHTNOVEL_CLI_DIR if set, the XDG
+    /// base directories otherwise, created if they don't exist yet
+    pub fn new(dir: Option<&str>) -> Result<Paths, Box<dyn std::error::Error>> {
+        let dir = dir
+            .map(|x| x.to_string())
+            .or_else(|| env::var(DIR_VAR).ok().filter(|x| !x.is_empty()));
+        let paths = match dir {
+            Some(dir) => Paths {
+                config_dir: PathBuf::from(&dir),
+                data_dir: PathBuf::from(&dir),
+                cache_dir: PathBuf::from(&dir),
+                unmoved: Vec::new(),
+            },
+            None => {
+                let mut paths = Paths {
+                    config_dir: xdg_dir("XDG_CONFIG_HOME", ".config")?,
+                    data_dir: xdg_dir("XDG_DATA_HOME", ".local/share")?,
+                    cache_dir: xdg_dir("XDG_CACHE_HOME", ".cache")?,
+                    unmoved: Vec::new(),
+                };
+                paths.unmoved = paths.migrate();
+                paths
+            }
+        };
+        for dir in [&paths.config_dir, &paths.data_dir, &paths.cache_dir] {
+            fs::create_dir_all(dir).map_err(|err| {
+                format!("can't create {}: {}", dir.display(), err)
+            })?;
+        }
+        let library = paths.data_dir.join("novels.txt");
+        if !library.exists() {
+            fs::File::create(&library)?;
+        }
+        Ok(paths)
+    }
+
+    /// novels.txt in the data directory
+    pub fn library(&self) -> String {
+        self.data("novels.txt")
+    }
+
+    /// config.toml in the config directory
+    pub fn config(&self) -> String {
+        self.config_dir
+            .join("config.toml")
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// a file of the library in the data directory
+    pub fn data(&self, name: &str) -> String {
+        self.data_dir.join(name).to_str().unwrap().to_string()
+    }
+
+    /// directory of the downloaded chapters
+    pub fn chapters(&self) -> PathBuf {
+        self.cache_dir.join("chapters")
+    }
+
+    // older versions kept everything in the config directory, move the
+    // library and the downloaded chapters where they belong now, returns
+    // the ones that couldn't be moved
+    fn migrate(&self) -> Vec<(PathBuf, PathBuf)> {
+        let old_dir = &self.config_dir;
+        if old_dir == &self.data_dir
+            || !old_dir.join("novels.txt").exists()
+            || self.data_dir.join("novels.txt").exists()
+        {
+            return Vec::new();
+        }
+        let _ = fs::create_dir_all(&self.data_dir);
+        let _ = fs::create_dir_all(&self.cache_dir);
+        let mut moves = DATA_FILES
+            .iter()
+            .map(|x| (old_dir.join(x), self.data_dir.join(x)))
+            .collect::<Vec<_>>();
+        moves.push((old_dir.join("chapters"), self.chapters()));
+        moves
+            .into_iter()
+            .filter(|(from, to)| !move_path(from, to))
+            .collect()
+    }
+}
+
+// $XDG_..._HOME/lightnovel-cli, or ~/<fallback>/lightnovel-cli if the
+// variable isn't set to an absolute path
+fn xdg_dir(
+    variable: &str,
+    fallback: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Ok(dir) = env::var(variable) {
+        if Path::new(&dir).is_absolute() {
+            return Ok(Path::new(&dir).join(APP));
+        }
+    }
+    match env::var("HOME") {
+        Ok(home) if !home.is_empty() => {
+            Ok(Path::new(&home).join(fallback).join(APP))
+        }
+        _ => Err(format!(
+            "can't find a home directory to keep the library in, set HOME, \
+             {} or pass --config-dir",
+            variable
+        )
+        .into()),
+    }
+}
+
+// rename, or copy and remove if from is on another file system, false if
+// from is still there and to isn't
+fn move_path(from: &Path, to: &Path) -> bool {
+    if !from.exists() || to.exists() || fs::rename(from, to).is_ok() {
+        return true;
+    }
+    if from.is_file() && fs::copy(from, to).is_ok() {
+        let _ = fs::remove_file(from);
+        return true;
+    }
+    false
+}
@@ -1,24 +1,79 @@
-use scraper::{Html, Selector};
-use std::{
-    fs::File,
-    io::{BufRead, BufReader, Read, Write},
-};
+use chrono::{Datelike, Duration, Local};
+use std::path::PathBuf;
 
-use ncurses::*;
+use crate::ui::*;
+
+use crate::input::{Action, LineEditor};
+use crate::list::{fit, pad, ListView};
+use lightnovel_cli::autoscroll::AutoScroll;
+use lightnovel_cli::cache;
+use lightnovel_cli::chapter::{self, Block, Chapter, Span, Style};
+use lightnovel_cli::config::{self, Config};
+use lightnovel_cli::cover;
+use lightnovel_cli::fuzzy;
+use lightnovel_cli::highlights::{self, Highlight, Place};
+use lightnovel_cli::history::{self, Session, Stats};
+use lightnovel_cli::layout::{self, Run};
+use lightnovel_cli::library::{self, Entry, Sort, Status};
+use lightnovel_cli::net;
+use lightnovel_cli::novel::{self, NovelInfo};
+use lightnovel_cli::paths::Paths;
+use lightnovel_cli::search::{self, SearchPage, SearchResult};
+use lightnovel_cli::search_history;
+use lightnovel_cli::tts::ReadAloud;
+
+// keep at most this many chapters in doc while scrolling continuously
+const MAX_CHAPTERS: usize = 3;
+
+const VISUAL_STATUS: &str =
+    "-- VISUAL -- j/k: extend, f: part of it, enter: save, esc: cancel";
 
 pub struct Screen {
-    raw_doc: Vec<String>,
+    raw_doc: Vec<Block>,
     doc: Vec<String>,
     maxx: i32,
     maxy: i32,
     curr_bot: i32,
     curr_top: i32,
     pub url: String,
+    // novels.txt
     path: String,
+    paths: Paths,
+    // block of raw_doc each line of doc belongs to, -1 for padding
+    line_para: Vec<i32>,
+    // index in chapters each line of doc belongs to
+    line_chapter: Vec<usize>,
+    // styled ranges of each line of doc
+    line_runs: Vec<Vec<Run>>,
+    // chapters laid out in doc, more than one in continuous mode
+    chapters: Vec<Chapter>,
+    // chapter at the top of the screen, the one self.url points to
+    current: usize,
+    continuous: bool,
+    // chapters past the first or last one that couldn't be loaded, not
+    // tried again until another chapter is opened
+    dead_ends: Vec<String>,
+    highlights: Vec<Highlight>,
+    // where the highlights are in each of chapters
+    chapter_highlights: Vec<Vec<Place>>,
+    // (anchor, cursor) paragraphs while in visual selection mode
+    selection: Option<(usize, usize)>,
+    config: Config,
+    read_aloud: Option<ReadAloud>,
+    autoscroll: Option<AutoScroll>,
+    // only novels with this status and tag are shown on the welcome screen
+    shelf: Option<Status>,
+    shelf_tag: Option<String>,
+    // chapter being read, written to the history when it's closed
+    session: Option<Session>,
+    // chapters of the open novel as of the last library refresh, 0 if unknown
+    chapter_count: i32,
+    // shown once in the status line of the library, like a broken config
+    notice: String,
 }
 
 impl Screen {
-    pub async fn new(path: String) -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new(paths: Paths) -> Result<Self, Box<dyn std::error::Error>> {
         let mut s = Self {
             raw_doc: vec![],
             doc: vec![],
@@ -27,50 +82,109 @@ impl Screen {
             curr_bot: -1,
             curr_top: 0,
             url: String::new(),
-            path,
+            path: paths.library(),
+            paths,
+            highlights: vec![],
+            chapter_highlights: vec![],
+            line_para: vec![],
+            line_chapter: vec![],
+            line_runs: vec![],
+            chapters: vec![],
+            current: 0,
+            continuous: false,
+            dead_ends: vec![],
+            selection: None,
+            config: Config::default(),
+            read_aloud: None,
+            autoscroll: None,
+            shelf: None,
+            shelf_tag: None,
+            session: None,
+            chapter_count: 0,
+            notice: String::new(),
         };
 
-        setlocale(LcCategory::all, "");
         initscr();
-        keypad(stdscr(), true);
-        use_default_colors();
-        start_color();
         init_pair(1, COLOR_GREEN, -1);
-        noecho();
-        raw();
+        init_pair(2, COLOR_BLACK, COLOR_YELLOW);
+        init_pair(3, COLOR_CYAN, -1);
+        init_pair(4, COLOR_MAGENTA, -1);
         clear();
-        getmaxyx(stdscr(), &mut s.maxy, &mut s.maxx);
+        getmaxyx(&mut s.maxy, &mut s.maxx);
         s.maxy -= 1;
         s.curr_bot = s.maxy;
-        s.draw_welcome_screen().await?;
+        s.highlights = highlights::load(&s.highlights_path());
+        s.config = match config::load(&s.config_path()) {
+            Ok(config) => config,
+            Err(err) => {
+                s.notice = format!(" {}, using the defaults", err);
+                Config::default()
+            }
+        };
+        // main already configured the network, an error was shown there
+        let _ = s.apply_config();
+        // give the terminal back before an error is printed
+        let result = s.draw_welcome_screen().await;
         endwin();
+        result?;
         Ok(s)
     }
 
     pub async fn draw_chapter_screen(
         &mut self,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+        let result = self.read_chapter_screen().await;
+        // leaving with an error still ends the reading session and stops
+        // speaking or scrolling
+        self.stop_read_aloud();
+        self.stop_autoscroll();
+        self.end_session();
+        result
+    }
+
+    async fn read_chapter_screen(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        curs_set(false);
 
         clear();
-        keypad(stdscr(), true);
 
         self.draw(true);
 
         let mut ch = getch();
 
         loop {
-            if is_term_resized(self.maxy, self.maxx) {
+            // maxy is the last row above the status line
+            if is_term_resized(self.maxy + 1, self.maxx) {
                 let tempy = self.maxy;
-                getmaxyx(stdscr(), &mut self.maxy, &mut self.maxx);
+                getmaxyx(&mut self.maxy, &mut self.maxx);
                 self.maxy -= 1;
                 self.curr_bot += self.maxy - tempy;
                 self.parse_doc();
-                // wrefresh(stdscr());
                 clear();
                 self.draw(true);
             }
 
+            if self.selection.is_some() {
+                self.handle_visual_key(ch);
+                ch = getch();
+                continue;
+            }
+
+            if self.read_aloud.is_some()
+                && self.handle_read_aloud_key(ch).await?
+            {
+                ch = getch();
+                continue;
+            }
+
+            if self.autoscroll.is_some()
+                && self.handle_autoscroll_key(ch).await?
+            {
+                ch = getch();
+                continue;
+            }
+
             match ch as u32 {
                 // q
                 113 => {
@@ -82,58 +196,117 @@ impl Screen {
                 }
                 // j or down_arrow
                 106 | 258 => {
+                    self.scroll_chapter(1).await?;
                     clear();
-                    self.scroll(1);
                     self.draw(true);
                     ch = getch();
                 }
                 // d
                 100 => {
+                    self.scroll_chapter(self.maxy / 2).await?;
                     clear();
-                    self.scroll(self.maxy / 2);
                     self.draw(true);
                     ch = getch();
                 }
                 // k or up_arrow
                 107 | 259 => {
+                    self.scroll_chapter(-1).await?;
                     clear();
-                    self.scroll(-1);
                     self.draw(true);
                     ch = getch();
                 }
                 // u
                 117 => {
+                    self.scroll_chapter(-(self.maxy / 2)).await?;
                     clear();
-                    self.scroll(-(self.maxy / 2));
                     self.draw(true);
                     ch = getch();
                 }
+                // c
+                99 => {
+                    self.continuous = !self.continuous;
+                    self.draw_status(if self.continuous {
+                        "continuous scrolling on"
+                    } else {
+                        "continuous scrolling off"
+                    });
+                    ch = getch();
+                }
                 // h or left_arrow
                 104 | 260 => {
                     // move 1 chapter back
-                    clear();
-                    // reset screen to top of page
-                    self.curr_top = 0;
-                    self.curr_bot = self.maxy;
-
-                    // self.url = self.prev_url.clone();
-                    self.change_chapter(-1);
-                    self.get_doc().await?;
-                    self.draw(true);
+                    if let Err(err) = self.goto_chapter(-1).await {
+                        self.draw_status(&format!(
+                            "no previous chapter: {}",
+                            err
+                        ));
+                    }
                     ch = getch();
                 }
                 // l or right_arrow
                 108 | 261 => {
                     // move one chapter front
+                    if let Err(err) = self.goto_chapter(1).await {
+                        self.draw_status(&format!("no next chapter: {}", err));
+                    }
+                    ch = getch();
+                }
+                // r
+                114 => {
+                    self.stop_autoscroll();
+                    self.start_read_aloud();
+                    ch = getch();
+                }
+                // a
+                97 => {
+                    self.stop_read_aloud();
+                    self.autoscroll =
+                        Some(AutoScroll::new(&self.config.autoscroll));
+                    self.update_timeout();
+                    let status =
+                        autoscroll_status(self.autoscroll.as_ref().unwrap());
+                    self.draw_status(&status);
+                    ch = getch();
+                }
+                // v
+                118 => {
+                    // start selecting from the first paragraph on screen
+                    if let Some(first) = self.first_paragraph_on_screen() {
+                        self.selection = Some((first, first));
+                        clear();
+                        self.draw(true);
+                        self.draw_status(VISUAL_STATUS);
+                    }
+                    ch = getch();
+                }
+                // e
+                101 => {
+                    let novel = self.novel_title();
+                    let name =
+                        format!("{}-highlights.md", novel.replace(' ', "-"));
+                    let out_path = self
+                        .prompt_with("export to: ", &self.paths.data(&name))
+                        .map(|x| x.trim().to_string())
+                        .filter(|x| !x.is_empty());
+                    let message = match out_path {
+                        Some(out_path) => match highlights::export_markdown(
+                            &self.highlights,
+                            &novel,
+                            &out_path,
+                        ) {
+                            Ok(count) => format!(
+                                "exported {} highlights to {}",
+                                count, out_path
+                            ),
+                            Err(err) => {
+                                format!("error exporting highlights: {}", err)
+                            }
+                        },
+                        None => String::new(),
+                    };
                     clear();
-                    // reset screen to top of page
-                    self.curr_top = 0;
-                    self.curr_bot = self.maxy;
-
-                    // self.url = self.next_url.clone();
-                    self.change_chapter(1);
-                    self.get_doc().await?;
                     self.draw(true);
+                    self.draw_status(&message);
                     ch = getch();
                 }
                 _ => {
@@ -144,6 +317,349 @@ impl Screen {
         Ok(())
     }
 
+    async fn goto_chapter(
+        &mut self,
+        offset: i32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        clear();
+        let (url, top, bot) = (self.url.clone(), self.curr_top, self.curr_bot);
+        // reset screen to top of page
+        self.curr_top = 0;
+        self.curr_bot = self.maxy;
+
+        self.change_chapter(offset);
+        if let Err(err) = self.get_doc().await {
+            // stay on the chapter that is open
+            self.url = url;
+            self.curr_top = top;
+            self.curr_bot = bot;
+            self.draw(true);
+            return Err(err);
+        }
+        if let Some(read_aloud) = &mut self.read_aloud {
+            read_aloud.speaker.stop();
+            read_aloud.paragraph = 0;
+            read_aloud.started = false;
+        }
+        self.draw(true);
+        Ok(())
+    }
+
+    fn first_paragraph_on_screen(&self) -> Option<usize> {
+        let bot = (self.curr_bot as usize).min(self.line_para.len());
+        (self.curr_top as usize..bot).find_map(|x| self.current_para(x))
+    }
+
+    // paragraph of the current chapter on a line of doc
+    fn current_para(&self, index: usize) -> Option<usize> {
+        match (self.line_para.get(index), self.line_chapter.get(index)) {
+            (Some(para), Some(chapter))
+                if *para >= 0 && *chapter == self.current =>
+            {
+                Some(*para as usize)
+            }
+            _ => None,
+        }
+    }
+
+    // scroll, loading the chapter before or after when scrolling past the
+    // edges of doc in continuous mode
+    async fn scroll_chapter(
+        &mut self,
+        scroll_by: i32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.continuous {
+            if scroll_by > 0
+                && self.curr_bot + scroll_by > self.doc.len() as i32
+            {
+                self.extend_doc(1).await;
+            } else if scroll_by < 0 && self.curr_top + scroll_by < 0 {
+                self.extend_doc(-1).await;
+            }
+        }
+        self.scroll(scroll_by);
+
+        // track the chapter at the top of the screen
+        let current = match self.line_chapter.get(self.curr_top.max(0) as usize)
+        {
+            Some(current) => *current,
+            None => return Ok(()),
+        };
+        if current != self.current {
+            self.current = current;
+            self.url = self.chapters[current].url.clone();
+            self.raw_doc = self.chapters[current].blocks.clone();
+            self.update_novels();
+        }
+        Ok(())
+    }
+
+    // append (offset 1) or prepend (offset -1) a chapter to doc
+    async fn extend_doc(&mut self, offset: i32) {
+        let edge = if offset > 0 {
+            self.chapters.last()
+        } else {
+            self.chapters.first()
+        };
+        let url = match edge
+            .and_then(|x| novel::offset_chapter_url(&x.url, offset))
+        {
+            Some(url) if !self.dead_ends.contains(&url) => url,
+            _ => return,
+        };
+        let chapter = match self.load_chapter(&url).await {
+            Ok(chapter) => chapter,
+            // first or last chapter, nothing to add
+            Err(_) => {
+                self.dead_ends.push(url);
+                return;
+            }
+        };
+
+        // remember where the top of the screen is inside its chapter
+        let top_url = self.chapters[self.current].url.clone();
+        let top_offset = self.curr_top - self.chapter_start(self.current);
+
+        if offset > 0 {
+            self.chapters.push(chapter);
+            if self.chapters.len() > MAX_CHAPTERS {
+                self.chapters.remove(0);
+            }
+        } else {
+            self.chapters.insert(0, chapter);
+            self.chapters.truncate(MAX_CHAPTERS);
+        }
+        self.parse_doc();
+
+        self.current = self
+            .chapters
+            .iter()
+            .position(|x| x.url == top_url)
+            .unwrap_or(0);
+        self.curr_top = self.chapter_start(self.current) + top_offset;
+        self.curr_bot = self.curr_top + self.maxy;
+    }
+
+    fn chapter_start(&self, chapter: usize) -> i32 {
+        self.line_chapter
+            .iter()
+            .position(|x| *x == chapter)
+            .unwrap_or(0) as i32
+    }
+
+    fn start_read_aloud(&mut self) {
+        let first = self.first_paragraph_on_screen().unwrap_or(0);
+        self.read_aloud = Some(ReadAloud::new(&self.config.tts, first));
+        self.update_timeout();
+    }
+
+    fn stop_read_aloud(&mut self) {
+        self.read_aloud = None;
+        self.update_timeout();
+    }
+
+    fn stop_autoscroll(&mut self) {
+        self.autoscroll = None;
+        self.update_timeout();
+    }
+
+    // block on getch unless something has to happen without a key press,
+    // otherwise wake up regularly so getch returns ERR
+    fn update_timeout(&self) {
+        if self.read_aloud.is_some() || self.autoscroll.is_some() {
+            timeout(100);
+        } else {
+            timeout(-1);
+        }
+    }
+
+    // returns true if the key was used by the auto scroll mode
+    async fn handle_autoscroll_key(
+        &mut self,
+        ch: i32,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let autoscroll = self.autoscroll.as_mut().unwrap();
+        match ch {
+            // no key pressed before the timeout
+            ERR => {
+                if !autoscroll.is_due() {
+                    return Ok(true);
+                }
+                let by_paragraph = autoscroll.by_paragraph;
+                if !self.continuous && self.curr_bot >= self.doc.len() as i32 {
+                    if let Err(err) = self.goto_chapter(1).await {
+                        self.stop_autoscroll();
+                        self.draw_status(&format!(
+                            "no next chapter, stopped auto scroll: {}",
+                            err
+                        ));
+                        return Ok(true);
+                    }
+                } else {
+                    let scroll_by = if by_paragraph {
+                        self.next_paragraph_offset()
+                    } else {
+                        1
+                    };
+                    self.scroll_chapter(scroll_by).await?;
+                    clear();
+                    self.draw(true);
+                }
+            }
+            // space
+            32 => autoscroll.toggle_pause(),
+            // +
+            43 => autoscroll.faster(),
+            // -
+            45 => autoscroll.slower(),
+            // a
+            97 => {
+                self.stop_autoscroll();
+                clear();
+                self.draw(true);
+                return Ok(true);
+            }
+            _ => return Ok(false),
+        }
+        let status = autoscroll_status(self.autoscroll.as_ref().unwrap());
+        self.draw_status(&status);
+        Ok(true)
+    }
+
+    // lines between the top of the screen and the start of the next paragraph
+    fn next_paragraph_offset(&self) -> i32 {
+        let top = self.curr_top as usize;
+        for index in top + 1..self.line_para.len() {
+            let para = self.line_para[index];
+            if para >= 0
+                && (para != self.line_para[index - 1]
+                    || self.line_chapter[index] != self.line_chapter[index - 1])
+            {
+                return (index - top) as i32;
+            }
+        }
+        1
+    }
+
+    // returns true if the key was used by the read aloud mode
+    async fn handle_read_aloud_key(
+        &mut self,
+        ch: i32,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let read_aloud = self.read_aloud.as_mut().unwrap();
+        match ch {
+            // no key pressed before the timeout
+            ERR => {
+                if read_aloud.is_sleep_due() {
+                    self.stop_read_aloud();
+                    clear();
+                    self.draw(true);
+                    self.draw_status("sleep timer ended, stopped reading");
+                    return Ok(true);
+                }
+                if read_aloud.paused || read_aloud.speaker.is_speaking() {
+                    if read_aloud.paused {
+                        let status = read_aloud_status(read_aloud);
+                        self.draw_status(&status);
+                    }
+                    return Ok(true);
+                }
+                if read_aloud.started {
+                    read_aloud.paragraph += 1;
+                }
+                if read_aloud.paragraph >= self.raw_doc.len() {
+                    if let Err(err) = self.goto_chapter(1).await {
+                        self.stop_read_aloud();
+                        self.draw_status(&format!(
+                            "no next chapter, stopped reading: {}",
+                            err
+                        ));
+                        return Ok(true);
+                    }
+                }
+                self.speak_paragraph();
+            }
+            // space
+            32 => {
+                read_aloud.paused = !read_aloud.paused;
+                if read_aloud.paused {
+                    read_aloud.speaker.stop();
+                    let status = read_aloud_status(read_aloud);
+                    self.draw_status(&status);
+                } else {
+                    self.speak_paragraph();
+                }
+            }
+            // + or -
+            43 | 45 => {
+                if ch == 43 {
+                    read_aloud.speed += 25;
+                } else if read_aloud.speed > 50 {
+                    read_aloud.speed -= 25;
+                }
+                if !read_aloud.paused {
+                    self.speak_paragraph();
+                }
+            }
+            // t
+            116 => {
+                read_aloud.cycle_sleep_timer();
+                let status = read_aloud_status(read_aloud);
+                self.draw_status(&status);
+            }
+            // r
+            114 => {
+                self.stop_read_aloud();
+                clear();
+                self.draw(true);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    // (re)start speaking the current paragraph and bring it on screen
+    fn speak_paragraph(&mut self) {
+        let read_aloud = self.read_aloud.as_mut().unwrap();
+        let paragraph = read_aloud.paragraph;
+        let text = match self.raw_doc.get(paragraph) {
+            Some(block) => block.text(),
+            None => return,
+        };
+        let speed = read_aloud.speed;
+        read_aloud.started = true;
+        if let Err(err) = read_aloud.speaker.speak(&text, speed) {
+            self.stop_read_aloud();
+            clear();
+            self.draw(true);
+            self.draw_status(&format!(
+                "error starting text to speech: {}",
+                err
+            ));
+            return;
+        }
+        let status = read_aloud_status(read_aloud);
+
+        self.scroll_to_paragraph(paragraph);
+        clear();
+        self.draw(true);
+        self.draw_status(&status);
+    }
+
+    fn scroll_to_paragraph(&mut self, paragraph: usize) {
+        let lines = (0..self.line_para.len())
+            .filter(|x| self.current_para(*x) == Some(paragraph))
+            .map(|x| x as i32)
+            .collect::<Vec<_>>();
+        if let (Some(first), Some(last)) = (lines.first(), lines.last()) {
+            if *first < self.curr_top {
+                self.scroll(first - self.curr_top);
+            } else if *last >= self.curr_bot {
+                self.scroll(last - self.curr_bot + 1);
+            }
+        }
+    }
+
     pub fn scroll(&mut self, scroll_by: i32) {
         if scroll_by > 0 {
             self.curr_bot += scroll_by;
@@ -164,7 +680,10 @@ impl Screen {
     }
 
     pub fn draw(&mut self, show_completion: bool) {
-        for (index, line) in (&self.doc).iter().enumerate() {
+        if show_completion {
+            self.track_session();
+        }
+        for (index, line) in self.doc.iter().enumerate() {
             if index < self.curr_top as usize {
                 continue;
             }
@@ -173,712 +692,1694 @@ impl Screen {
             }
 
             let mut temp = line.clone();
-            // add completition percentage
-            if show_completion {
-                if index as i32 == self.curr_top {
+            // add progress of the chapter at the top of the screen
+            if show_completion && index as i32 == self.curr_top {
+                let progress = self.progress_text();
+                if !progress.is_empty() {
                     temp.pop();
-                    let length = temp.chars().collect::<Vec<_>>().len();
+                    let length = temp.chars().count() + progress.len() + 1;
                     temp.push_str(
-                        " ".repeat((self.maxx - length as i32 - 5) as usize)
-                            .as_str(),
-                    );
-                    let mut percentage = String::new();
-                    let mut percentage_val =
-                        ((self.curr_bot as f32 / self.doc.len() as f32)
-                            * 100.00) as i32;
-                    if percentage_val > 100 {
-                        percentage_val = 100;
-                    }
-
-                    percentage.push_str(
-                        format!("{:0.3}", percentage_val.to_string()).as_str(),
+                        &" ".repeat(
+                            (self.maxx as usize).saturating_sub(length),
+                        ),
                     );
-                    temp.push_str(percentage.as_str());
-                    temp.push_str("%\n");
+                    temp.push_str(&progress);
+                    temp.push('\n');
                 }
             }
-            // color title
-            if index == 1 {
-                attron(A_BOLD);
-                attron(COLOR_PAIR(1));
-                addstr(line.as_str());
-                attroff(COLOR_PAIR(1));
-                attroff(A_BOLD);
+            if show_completion {
+                self.draw_styled_line(index, line, &temp);
             } else {
                 addstr(temp.as_str());
             }
         }
     }
 
-    pub fn add_padding(&self, x: String) -> Vec<String> {
-        let mut vec: Vec<String> = Vec::new();
-        let mut result = String::new();
-        // calculate padding
-        let padding = self.maxx / 8;
-        let line_len = self.maxx - padding - padding;
-        result.push_str(" ".repeat(padding as usize).as_str());
-
-        let mut length = 0;
-        for word in x.split(' ') {
-            length += word.len() + 1;
-            if length >= line_len as usize {
-                // result.push_str(" ".repeat(padding as usize).as_str());
-                result.push_str("\n");
-                vec.push(result);
-                result = "".to_string();
-                length = word.len() + 1;
-                result.push_str(" ".repeat(padding as usize).as_str());
-                result.push_str(word);
-                result.push_str(" ");
-            } else {
-                result.push_str(word);
-                result.push_str(" ");
-            }
+    // how much of the chapter at the top of the screen has been on screen,
+    // 0.0 to 1.0
+    fn chapter_progress(&self) -> f32 {
+        let start = self.chapter_start(self.current);
+        let end = self
+            .line_chapter
+            .iter()
+            .rposition(|x| *x == self.current)
+            .map(|x| x as i32 + 1)
+            .unwrap_or(self.doc.len() as i32);
+        if end <= start {
+            return 1.0;
         }
-        // result.push_str(" ".repeat(padding as usize).as_str());
-        result.push_str("\n");
-        vec.push(result);
-        vec.push("\n".to_string());
-        vec
+        ((self.curr_bot - start) as f32 / (end - start) as f32).clamp(0.0, 1.0)
     }
 
-    pub fn parse_doc(&mut self) {
-        let mut result: Vec<String> = Vec::new();
-        result.push("\n".to_string());
-        for line in &self.raw_doc {
-            let parsed_line = self.add_padding(line.clone());
-            for x in parsed_line {
-                result.push(x);
+    // "42%", "ch 12/340" or both as set in the config
+    fn progress_text(&self) -> String {
+        let percent = format!("{}%", (self.chapter_progress() * 100.0) as i32);
+        let number = self
+            .chapters
+            .get(self.current)
+            .and_then(|x| novel::chapter_number(&x.url));
+        let chapter = match number {
+            Some(number) if self.chapter_count > 0 => {
+                format!("ch {}/{}", number, self.chapter_count)
             }
+            Some(number) => format!("ch {}", number),
+            None => String::new(),
+        };
+        match self.config.reader.progress.as_str() {
+            "none" => String::new(),
+            "chapter" => chapter,
+            "both" => format!("{} {}", chapter, percent).trim().to_string(),
+            _ => percent,
         }
-
-        let mut bottom_line = "<-- previous chapter (h)".to_string();
-        bottom_line.push_str(" ".repeat(self.maxx as usize - 44).as_str());
-        bottom_line.push_str("next chapter (l) -->");
-        result.push(bottom_line);
-        self.doc = result;
     }
 
-    pub async fn get_doc(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut result: Vec<String> = Vec::new();
-        let resp = match reqwest::get(&self.url).await {
-            Ok(x) => x.text().await?,
-            Err(_) => panic!("Connection refused!"),
-        };
-
-        let fragment = Html::parse_fragment(&resp);
-
-        let selector_content = Selector::parse(r#"div[class="txt "]"#).unwrap();
-        let article = fragment.select(&selector_content).next().unwrap();
-
-        let selector_title = Selector::parse(r#"h4"#).unwrap();
-        let title = match article.select(&selector_title).next() {
-            Some(item) => item.text().collect::<Vec<_>>()[0]
-                .to_string()
-                .trim()
-                .to_string(),
-            None => "".to_string(),
+    // start a new session when the chapter at the top of the screen changes
+    fn track_session(&mut self) {
+        let chapter = match self.chapters.get(self.current) {
+            Some(chapter) => chapter,
+            None => return,
         };
-        if title != "".to_string() {
-            result.push(title);
+        let reading = self
+            .session
+            .as_ref()
+            .is_some_and(|x| x.chapter_url == chapter.url);
+        if !reading {
+            let url = chapter.url.clone();
+            let words = chapter
+                .blocks
+                .iter()
+                .map(|x| x.text().split_whitespace().count())
+                .sum();
+            self.end_session();
+            self.prefetch(&url);
+            self.session = Some(Session {
+                novel: library::title_from_url(&url).unwrap_or_default(),
+                chapter_url: url,
+                start: library::now(),
+                words,
+                read: 0.0,
+            });
         }
-
-        let selector = Selector::parse(r#"p"#).unwrap();
-        for element in article.select(&selector) {
-            let line = element
-                .text()
-                .collect::<Vec<&str>>()
-                .join("")
-                .trim()
-                .to_string();
-
-            if line.is_empty() {
-                continue;
-            }
-            result.push(line);
+        let read = self.chapter_progress();
+        if let Some(session) = &mut self.session {
+            session.read = session.read.max(read);
         }
-        self.raw_doc = result.clone();
-        self.doc = result;
-        self.parse_doc();
-
-        Ok(())
     }
 
-    pub fn update_novels(&self) {
-        // get novel title
-        let title =
-            self.url.split("/").collect::<Vec<_>>()[3].replace("-", " ");
-
-        // read file
-        let mut line = String::new();
-        let mut file_buffer = File::options()
-            .read(true)
-            .open(&self.path)
-            .expect("error reading config file");
-
-        file_buffer
-            .read_to_string(&mut line)
-            .expect("error reading config file");
-
-        drop(file_buffer);
-
-        // find list of all titles
-        let mut list_of_titles = line
-            .split("\n")
-            .into_iter()
-            .map(|x| x.split("#").collect::<Vec<_>>())
+    // download the chapters after url in the background so they open
+    // without waiting
+    fn prefetch(&self, url: &str) {
+        let depth = self.config.reader.prefetch as i32;
+        if depth == 0 || novel::chapter_number(url).is_none() {
+            return;
+        }
+        let urls = (1..=depth)
+            .filter_map(|x| novel::offset_chapter_url(url, x))
             .collect::<Vec<_>>();
+        let dir = self.cache_dir();
+        let max_bytes = self.config.cache.max_mb << 20;
+        tokio::spawn(async move {
+            for url in urls {
+                // past the latest chapter
+                if cache::prefetch(&dir, &url).await.is_err() {
+                    break;
+                }
+            }
+            if max_bytes > 0 {
+                cache::prune(&dir, max_bytes);
+            }
+        });
+    }
 
-        // if title is new add it to list else update the url of pre-existing novel
-        let mut is_title_present = false;
-        for i in 0..list_of_titles.len() {
-            if list_of_titles[i][0] == title {
-                list_of_titles[i][1] = self.url.trim();
-                // push most recent novel to top of list
-                let removed_title = list_of_titles.remove(i);
-                list_of_titles.insert(0, removed_title);
+    fn end_session(&mut self) {
+        if let Some(session) = self.session.take() {
+            let record = session.finish(library::now());
+            self.report(history::add(&self.history_path(), &record));
+        }
+    }
 
-                is_title_present = true;
-                break;
+    // draw a chapter line with the formatting of its text and the selection,
+    // read aloud or highlight attribute on top, leaving the padding and
+    // percentage unstyled
+    fn draw_styled_line(&self, index: usize, line: &str, temp: &str) {
+        let chars = temp.chars().collect::<Vec<_>>();
+        let mut attrs: Vec<attr_t> = vec![0; chars.len()];
+
+        let line_attr = self.line_attr(index);
+        if line_attr != 0 {
+            let start = line.chars().take_while(|x| *x == ' ').count();
+            let end = line.trim_end().chars().count();
+            for attr in attrs.iter_mut().take(end).skip(start) {
+                *attr |= line_attr;
             }
         }
-        if !is_title_present {
-            list_of_titles.insert(0, vec![&title, self.url.as_str()])
+        if let Some(runs) = self.line_runs.get(index) {
+            for run in runs {
+                let mut style = style_attr(run.style);
+                // pair numbers can't be combined, the read aloud or highlight
+                // color of the line wins over the one of the text
+                if line_attr & A_COLOR != 0 {
+                    style &= !A_COLOR;
+                }
+                let end = run.end.min(attrs.len());
+                for attr in attrs.iter_mut().take(end).skip(run.start) {
+                    *attr |= style;
+                }
+            }
         }
 
-        let mut file_buffer = File::options()
-            .write(true)
-            .truncate(true)
-            .open(&self.path)
-            .expect("error reading config file");
-
-        let mut file_content = list_of_titles
-            .into_iter()
-            .map(|x| x.join("#"))
-            .collect::<Vec<_>>()
-            .join("\n");
-        file_content = file_content.trim().to_string();
-
-        write!(&mut file_buffer, "{}", file_content.as_str())
-            .expect("error writing to file");
+        let mut start = 0;
+        for end in 1..=chars.len() {
+            if end == chars.len() || attrs[end] != attrs[start] {
+                let part = chars[start..end].iter().collect::<String>();
+                if attrs[start] == 0 {
+                    addstr(&part);
+                } else {
+                    attron(attrs[start]);
+                    addstr(&part);
+                    attroff(attrs[start]);
+                }
+                start = end;
+            }
+        }
     }
 
-    pub async fn draw_welcome_screen(
-        &mut self,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let (mut result, mut chapter_urls) = self.make_welcome_screen();
-        let x = 2;
-        let mut y = 3;
-
-        self.draw(false);
-        wmove(stdscr(), y, x);
-        wrefresh(stdscr());
-
-        let mut ch = getch();
-
-        loop {
-            match ch {
-                113 => {
-                    clear();
-                    break;
+    fn line_attr(&self, index: usize) -> attr_t {
+        if let Some(para) = self.current_para(index) {
+            if let Some((anchor, cursor)) = self.selection {
+                if para >= anchor.min(cursor) && para <= anchor.max(cursor) {
+                    return A_REVERSE;
                 }
-                106 | 258 => {
-                    // clear();
-                    if y as i32 == self.maxy - 1 {
-                        clear();
-                        self.scroll(1);
-                        self.draw(false);
-                        wmove(stdscr(), y, x);
-                        wrefresh(stdscr());
-                    }
-                    // if you have reached bottom of all options, stop scrolling
-                    else if y == 2 + chapter_urls.len() as i32 {
-                    } else {
-                        y += 1;
-                        wmove(stdscr(), y, x);
-                        wrefresh(stdscr());
-                    }
-                    ch = getch();
+            }
+            if let Some(read_aloud) = &self.read_aloud {
+                if read_aloud.started && read_aloud.paragraph == para {
+                    return A_BOLD | COLOR_PAIR(3);
                 }
-                107 | 259 => {
-                    if y as i32 == 1 {
-                        clear();
-                        self.scroll(-1);
-                        self.draw(false);
-                        wmove(stdscr(), y, x);
-                        wrefresh(stdscr());
-                    } else {
-                        y -= 1;
-                        wmove(stdscr(), y, x);
-                        wrefresh(stdscr());
+            }
+        }
+        let (para, highlights) =
+            match (self.line_para.get(index), self.line_chapter.get(index)) {
+                (Some(para), Some(chapter)) if *para >= 0 => {
+                    match self.chapter_highlights.get(*chapter) {
+                        Some(highlights) => (*para as usize, highlights),
+                        None => return 0,
                     }
-                    ch = getch();
                 }
-                // s
-                115 => {
-                    self.display_search_screen().await?;
-                    self.curr_bot = self.maxy;
-                    self.curr_top = 0;
-                    (result, chapter_urls) = self.make_welcome_screen();
-                    clear();
-                    self.draw(false);
-                    wmove(stdscr(), y, x);
-                    wrefresh(stdscr());
-                    curs_set(CURSOR_VISIBILITY::CURSOR_VISIBLE);
-                    ch = getch();
+                _ => return 0,
+            };
+        // parts of a paragraph are marked in its runs instead
+        if highlights
+            .iter()
+            .any(|x| x.span.is_none() && para >= x.start && para <= x.end)
+        {
+            return COLOR_PAIR(2);
+        }
+        0
+    }
+
+    fn draw_status(&self, message: &str) {
+        mvaddstr(self.maxy, 0, message);
+        clrtoeol();
+        refresh();
+    }
+
+    fn handle_visual_key(&mut self, ch: i32) {
+        let (anchor, mut cursor) = self.selection.unwrap();
+        match ch {
+            // j or down_arrow
+            106 | 258 => {
+                if cursor + 1 < self.raw_doc.len() {
+                    cursor += 1;
                 }
-                10 => {
-                    let mut line: Vec<chtype> = Vec::new();
-                    if y != 1 {
-                        mvinchnstr(y, 0, &mut line, self.maxx + 1);
-                        wmove(stdscr(), y, x);
-                        wrefresh(stdscr());
-                        let mut chapter = line
-                            .into_iter()
-                            .map(|x| char::from_u32(x).unwrap())
-                            .collect::<String>();
-                        chapter.pop();
-                        let chapter = chapter.trim().to_string();
-
-                        let mut is_novel_found = false;
-                        // get novel url
-                        for id in 3..result.len() {
-                            if result[id].trim() == chapter {
-                                self.url = (&chapter_urls[id - 3]).clone();
-                                self.get_doc().await?;
-
-                                // draw ln screen
-                                is_novel_found = true;
-                                self.draw_chapter_screen().await?;
-                                y = 3;
-                                wmove(stdscr(), 3, 2);
-                                wrefresh(stdscr());
-                                break;
-                            }
-                        }
-                        if is_novel_found {
-                            (result, chapter_urls) = self.make_welcome_screen();
-                            clear();
-                            self.draw(false);
-                            wmove(stdscr(), y, x);
-                            wrefresh(stdscr());
-                            curs_set(CURSOR_VISIBILITY::CURSOR_VISIBLE);
-                        }
-                    }
-                    ch = getch();
+            }
+            // k or up_arrow
+            107 | 259 => {
+                cursor = cursor.saturating_sub(1);
+            }
+            // enter
+            10 => {
+                let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+                self.save_highlight(start, end, None);
+                return;
+            }
+            // f
+            102 => {
+                if anchor != cursor {
+                    self.draw_status(
+                        "select a single paragraph to highlight part of it",
+                    );
+                    return;
                 }
-                _ => {
-                    ch = getch();
+                let part = self
+                    .prompt("part to highlight: ")
+                    .map(|x| x.trim().to_string())
+                    .filter(|x| !x.is_empty());
+                let Some(part) = part else {
+                    self.draw_status(VISUAL_STATUS);
+                    return;
+                };
+                let text = self.raw_doc[cursor].text();
+                match highlights::find_part(&text, &part) {
+                    Some(span) => {
+                        self.save_highlight(cursor, cursor, Some(span))
+                    }
+                    None => self.draw_status(&format!(
+                        "'{}' isn't in the selected paragraph",
+                        part
+                    )),
                 }
+                return;
+            }
+            // esc or v
+            27 | 118 => {
+                self.selection = None;
+                clear();
+                self.draw(true);
+                return;
             }
+            _ => return,
         }
-        Ok(())
-    }
+        self.selection = Some((anchor, cursor));
 
-    pub async fn display_search_screen(
-        &mut self,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.make_search_screen(None).await?;
-        let mut x = 3;
-        let mut y = 3;
+        // keep the paragraph under the cursor on screen
+        self.scroll_to_paragraph(cursor);
+        clear();
+        self.draw(true);
+        self.draw_status(VISUAL_STATUS);
+    }
 
+    // ask for a note and save the paragraphs start to end, or the chars span
+    // of paragraph start, as a highlight
+    fn save_highlight(
+        &mut self,
+        start: usize,
+        end: usize,
+        span: Option<(usize, usize)>,
+    ) {
+        let note = match self.prompt("note (optional): ") {
+            Some(note) => note,
+            None => {
+                self.selection = None;
+                clear();
+                self.draw(true);
+                return;
+            }
+        };
+        let text = match span {
+            Some((from, to)) => self.raw_doc[start]
+                .text()
+                .chars()
+                .skip(from)
+                .take(to - from)
+                .collect(),
+            None => self.raw_doc[start..=end]
+                .iter()
+                .map(|x| x.text())
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        let highlight = Highlight {
+            novel: self.novel_title(),
+            chapter_url: self.url.clone(),
+            start,
+            end,
+            span,
+            text,
+            note: note.trim().to_string(),
+        };
+        self.report(highlights::add(&self.highlights_path(), &highlight));
+        self.highlights.push(highlight);
+        // parts of paragraphs are laid out marked
+        self.parse_doc();
+        self.selection = None;
         clear();
-        noecho();
-        keypad(stdscr(), false);
-        self.draw(false);
-        wmove(stdscr(), y, x);
-        wrefresh(stdscr());
+        self.draw(true);
+        self.draw_status("highlight saved");
+    }
 
-        let search_result: Vec<[String; 3]>;
+    // read a line on the status line, None if cancelled
+    fn prompt(&self, message: &str) -> Option<String> {
+        self.prompt_with(message, "")
+    }
 
-        let mut ch = getch();
+    fn prompt_with(&self, message: &str, text: &str) -> Option<String> {
+        mvaddstr(self.maxy, 0, message);
+        let width = self.maxx - message.len() as i32 - 1;
+        let input =
+            LineEditor::new(text).read(self.maxy, message.len() as i32, width);
+        curs_set(false);
+        input
+    }
 
-        let mut novel: Vec<chtype> = Vec::new();
-        loop {
-            match ch {
-                10 => {
-                    mvinchnstr(y, 0, &mut novel, self.maxx + 1);
-                    let mut chapter = novel
-                        .into_iter()
-                        .map(|x| char::from_u32(x).unwrap())
-                        .collect::<Vec<_>>();
-                    chapter.remove(0);
-                    chapter.remove(0);
-                    chapter.remove(0);
-                    let chapter: String = chapter.into_iter().collect();
-                    let temp = self.make_search_screen(Some(chapter)).await?;
-                    match temp {
-                        Some(result) => search_result = result,
-                        None => panic!("error finding chapter"),
-                    }
-                    break;
+    fn config_path(&self) -> String {
+        self.paths.config()
+    }
+
+    fn search_history_path(&self) -> String {
+        self.paths.data("search_history.txt")
+    }
+
+    fn library_titles(&self) -> Vec<String> {
+        self.library()
+            .into_iter()
+            .map(|x| x.display_title().to_string())
+            .collect()
+    }
+
+    fn history_path(&self) -> String {
+        self.paths.data("history.txt")
+    }
+
+    fn highlights_path(&self) -> String {
+        self.paths.data("highlights.txt")
+    }
+
+    async fn load_chapter(
+        &self,
+        url: &str,
+    ) -> Result<Chapter, Box<dyn std::error::Error>> {
+        cache::chapter(&self.cache_dir(), url).await
+    }
+
+    fn cache_dir(&self) -> PathBuf {
+        self.paths.chapters()
+    }
+
+    // the library as it is on disk, empty if it can't be read
+    fn library(&self) -> Vec<Entry> {
+        library::load(&self.path).unwrap_or_default()
+    }
+
+    // a failed save shows in the status line, reading goes on without it
+    fn report(&self, result: std::io::Result<()>) {
+        if let Err(err) = result {
+            self.draw_status(&format!(" couldn't save: {}", err));
+        }
+    }
+
+    fn novel_title(&self) -> String {
+        library::title_from_url(&self.url).unwrap_or_default()
+    }
+
+    pub fn parse_doc(&mut self) {
+        self.place_highlights();
+        let mut result: Vec<String> = Vec::new();
+        let mut line_para: Vec<i32> = Vec::new();
+        let mut line_chapter: Vec<usize> = Vec::new();
+        let mut line_runs: Vec<Vec<Run>> = Vec::new();
+        for (chapter_index, chapter) in self.chapters.iter().enumerate() {
+            result.push("\n".to_string());
+            line_para.push(-1);
+            line_chapter.push(chapter_index);
+            line_runs.push(vec![]);
+            for (index, block) in chapter.blocks.iter().enumerate() {
+                let mut block = block.clone();
+                let spans = self.chapter_highlights[chapter_index]
+                    .iter()
+                    .filter(|x| x.start == index)
+                    .filter_map(|x| x.span);
+                for (from, to) in spans {
+                    block = layout::mark_block(&block, from, to);
                 }
-                _ => {
-                    if ch == 127 {
-                        if x < 4 {
-                            x = 4;
-                        }
-                        mvdelch(y, x - 1);
-                        x -= 1;
+                let parsed_line = layout::layout_block(
+                    &block,
+                    self.maxx,
+                    self.config.reader.width,
+                );
+                let length = parsed_line.len();
+                for (i, x) in parsed_line.into_iter().enumerate() {
+                    result.push(x.text);
+                    line_runs.push(x.runs);
+                    line_chapter.push(chapter_index);
+                    // last line of every paragraph is the empty separator
+                    if i + 1 == length {
+                        line_para.push(-1);
                     } else {
-                        x += 1;
-                        addch(ch as u32);
+                        line_para.push(index as i32);
                     }
-                    ch = getch();
                 }
             }
         }
-        // x = 3;
-        y = 4;
-        clear();
-        noecho();
-        keypad(stdscr(), true);
-        self.draw(false);
-        wmove(stdscr(), 4, 2);
-        wrefresh(stdscr());
-        ch = getch();
-
-        let mut prompt_len;
+
+        let mut bottom_line = "<-- previous chapter (h)".to_string();
+        bottom_line.push_str(" ".repeat(self.maxx as usize - 44).as_str());
+        bottom_line.push_str("next chapter (l) -->");
+        result.push(bottom_line);
+        line_para.push(-1);
+        line_chapter.push(self.chapters.len().saturating_sub(1));
+        line_runs.push(vec![]);
+        self.doc = result;
+        self.line_para = line_para;
+        self.line_chapter = line_chapter;
+        self.line_runs = line_runs;
+    }
+
+    // find the saved highlights in the chapters by their text
+    fn place_highlights(&mut self) {
+        self.chapter_highlights = self
+            .chapters
+            .iter()
+            .map(|chapter| {
+                self.highlights
+                    .iter()
+                    .filter(|x| x.chapter_url == chapter.url)
+                    .filter_map(|x| x.locate(&chapter.blocks))
+                    .collect()
+            })
+            .collect();
+    }
+
+    pub async fn get_doc(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let chapter = self.load_chapter(&self.url).await?;
+        self.raw_doc = chapter.blocks.clone();
+        self.chapters = vec![chapter];
+        self.dead_ends.clear();
+        self.current = 0;
+        let title = self.novel_title();
+        self.chapter_count = self
+            .library()
+            .iter()
+            .find(|x| x.title == title)
+            .map(|x| x.chapters)
+            .unwrap_or(0);
+        self.parse_doc();
+
+        Ok(())
+    }
+
+    pub fn update_novels(&self) {
+        self.add_to_library(&self.url);
+    }
+
+    // move the novel of url to the top of the library, adding it if new
+    fn add_to_library(&self, url: &str) {
+        self.report(library::add(&self.path, url, library::now()));
+    }
+
+    pub async fn draw_welcome_screen(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut list = ListView::new(self.make_welcome_screen(""));
+        let mut message = std::mem::take(&mut self.notice);
+
         loop {
+            clear();
+            self.draw_welcome_list(&mut list, "");
+            if !message.is_empty() {
+                self.draw_status(&std::mem::take(&mut message));
+            }
+            let ch = getch();
             match ch {
+                // q
                 113 => {
                     clear();
                     break;
                 }
-                106 | 258 => {
-                    // hit bottom of page, start scrolling down
-                    if y as i32 == self.maxy {
-                        clear();
-                        self.scroll(1);
-                        self.draw(false);
-                        wmove(stdscr(), y, 2);
-                        wrefresh(stdscr());
-                    } else if y == 3 + search_result.len() as i32 {
-                    } else {
-                        y += 1;
-                        wmove(stdscr(), y, 2);
-                        wrefresh(stdscr());
+                // /
+                47 => {
+                    if let Some(url) = self.filter_welcome_screen() {
+                        self.url = url;
+                        self.get_doc().await?;
+                        self.draw_chapter_screen().await?;
                     }
-                    ch = getch();
+                    list = ListView::new(self.make_welcome_screen(""));
                 }
-                107 | 259 => {
-                    // hit top of page, start scrolling up
-                    if y as i32 == 1 {
-                        clear();
-                        self.scroll(-1);
-                        self.draw(false);
-                        wmove(stdscr(), y, 2);
-                        wrefresh(stdscr());
-                    } else {
-                        y -= 1;
-                        wmove(stdscr(), y, 2);
-                        wrefresh(stdscr());
+                // s
+                115 => {
+                    self.display_search_screen().await?;
+                    list = ListView::new(self.make_welcome_screen(""));
+                }
+                // b
+                98 => {
+                    self.display_browse_screen().await?;
+                    list = ListView::new(self.make_welcome_screen(""));
+                }
+                // tab
+                9 => {
+                    // all novels, then each status in turn
+                    self.shelf = match self.shelf {
+                        None => Some(Status::Reading),
+                        Some(Status::Dropped) => None,
+                        Some(status) => Some(status.next()),
+                    };
+                    list = ListView::new(self.make_welcome_screen(""));
+                }
+                // #
+                35 => {
+                    let tag = self.prompt(" show tag (empty for all): ");
+                    if let Some(tag) = tag {
+                        self.shelf_tag = library::parse_tags(&tag).pop();
                     }
-                    ch = getch();
+                    list = ListView::new(self.make_welcome_screen(""));
                 }
-                10 => {
-                    let mut novel: Vec<chtype> = Vec::new();
-                    mvinchnstr(y, 0, &mut novel, self.maxx + 1);
-                    let mut chapter = novel
-                        .into_iter()
-                        .map(|x| char::from_u32(x).unwrap())
-                        .collect::<Vec<_>>();
-                    chapter.remove(0);
-                    chapter.remove(0);
-                    chapter.remove(0);
-                    chapter.remove(0);
-                    chapter.remove(0);
-                    chapter.pop();
-                    let chapter: String = chapter
-                        .into_iter()
-                        .collect::<String>()
-                        .trim()
-                        .to_string();
-
-                    let mut url = "https://freewebnovel.com".to_string();
-                    let mut max_chapter = -1;
-                    let mut is_novel_found = false;
-                    for element in &search_result {
-                        if element[0] == chapter {
-                            is_novel_found = true;
-                            url.push_str(element[1].as_str());
-                            max_chapter = element[2].parse::<i32>().unwrap();
+                // r
+                114 => {
+                    message = self.refresh_library().await;
+                    list = ListView::new(self.make_welcome_screen(""));
+                }
+                // S
+                83 => {
+                    self.display_stats_screen();
+                    list = ListView::new(self.make_welcome_screen(""));
+                }
+                // c
+                99 => {
+                    self.display_settings_screen();
+                    list = ListView::new(self.make_welcome_screen(""));
+                }
+                // o
+                111 => {
+                    let sort = Sort::parse(&self.config.library.sort).next();
+                    self.config.library.sort = sort.as_str().to_string();
+                    // remembered for the next start
+                    if let Err(err) = config::save_setting(
+                        &self.config_path(),
+                        "library.sort",
+                        &self.config,
+                    ) {
+                        message = format!(" sort not saved: {}", err);
+                    }
+                    list = ListView::new(self.make_welcome_screen(""));
+                }
+                // m
+                109 => {
+                    if let Some(novel) = list.selected_item() {
+                        self.report(library::update(
+                            &self.path,
+                            &novel.title,
+                            |x| x.status = x.status.next(),
+                        ));
+                        let selected = list.selected;
+                        list = ListView::new(self.make_welcome_screen(""));
+                        list.select(selected);
+                    }
+                }
+                // t
+                116 => {
+                    if let Some(novel) = list.selected_item() {
+                        let title = novel.title.clone();
+                        let tags = self.prompt_with(
+                            " tags (comma separated): ",
+                            &novel.tags.join(", "),
+                        );
+                        if let Some(tags) = tags {
+                            self.report(library::update(
+                                &self.path,
+                                &title,
+                                |x| x.tags = library::parse_tags(&tags),
+                            ));
                         }
+                        let selected = list.selected;
+                        list = ListView::new(self.make_welcome_screen(""));
+                        list.select(selected);
                     }
-                    if !is_novel_found {
-                        ch = getch();
-                        continue;
+                }
+                // i
+                105 => {
+                    if let Some(novel) = list.selected_item() {
+                        let url = novel::url_from_chapter(&novel.url);
+                        self.display_details_screen(&url).await?;
+                        list = ListView::new(self.make_welcome_screen(""));
                     }
-                    // url.push_str(&search_result[(y - 4) as usize][1].clone());
-                    self.url = url;
-                    // let max_chapter = search_result[(y - 4) as usize][2]
-                    //     .parse::<i32>()
-                    //     .unwrap();
+                }
+                10 => {
+                    if let Some(novel) = list.selected_item() {
+                        self.url = novel.url.clone();
+                        self.get_doc().await?;
+
+                        // draw ln screen
+                        self.draw_chapter_screen().await?;
+                        list = ListView::new(self.make_welcome_screen(""));
+                    }
+                }
+                _ => {
+                    list.handle_key(ch, self.maxy - 3);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_welcome_list(&self, list: &mut ListView<Entry>, filter: &str) {
+        curs_set(false);
+        let mut title = " Recently Read Novels".to_string();
+        if let Some(status) = self.shelf {
+            title.push_str(&format!(" ({})", status.label()));
+        }
+        if let Some(tag) = &self.shelf_tag {
+            title.push_str(&format!(" tagged '{}'", tag));
+        }
+        if !filter.is_empty() {
+            title.push_str(&format!(" matching '{}'", filter));
+        } else {
+            let sort = Sort::parse(&self.config.library.sort);
+            if sort != Sort::LastRead {
+                title.push_str(&format!(", by {}", sort.label()));
+            }
+        }
+        attron(A_BOLD);
+        attron(COLOR_PAIR(1));
+        mvaddstr(1, 0, &title);
+        attroff(COLOR_PAIR(1));
+        attroff(A_BOLD);
+
+        if list.items.is_empty() && filter.is_empty() {
+            if self.shelf.is_some() || self.shelf_tag.is_some() {
+                mvaddstr(3, 0, " No novels here, press (tab) to see others");
+            } else {
+                mvaddstr(3, 0, " No novels read yet, press (s) to search");
+            }
+        }
+        list.draw(3, self.maxy - 3, self.maxx, library_row);
+        refresh();
+    }
+
+    // look up the chapter count and title of every novel in the library to
+    // find new chapters, any key stops it. novels that can't be reached are
+    // skipped and counted in the returned message
+    async fn refresh_library(&self) -> String {
+        let entries = self.library();
+        let mut failed = 0;
+        let mut last_error = String::new();
+        timeout(0);
+        for (index, entry) in entries.iter().enumerate() {
+            if getch() != ERR {
+                break;
+            }
+            self.draw_status(&format!(
+                " checking {} of {}: {}, press any key to stop",
+                index + 1,
+                entries.len(),
+                entry.display_title()
+            ));
+            let url = novel::url_from_chapter(&entry.url);
+            let info = match novel::fetch(&url).await {
+                Ok(info) if info.chapters > 0 => info,
+                Ok(_) => continue,
+                Err(err) => {
+                    failed += 1;
+                    last_error = err.to_string();
+                    continue;
+                }
+            };
+            self.report(library::update(&self.path, &entry.title, |x| {
+                x.refresh(&info)
+            }));
+        }
+        timeout(-1);
+        if failed == 0 {
+            String::new()
+        } else {
+            format!(" couldn't check {} novels: {}", failed, last_error)
+        }
+    }
+
+    // totals, streaks, a calendar heatmap of words read per day and time
+    // spent on each novel
+    pub fn display_stats_screen(&mut self) {
+        let records = history::load(&self.history_path());
+        let stats = history::stats(&records);
+        let mut list = ListView::new(stats.novels.clone());
+        let mut message = String::new();
+        curs_set(false);
+
+        loop {
+            clear();
+            draw_header(" Reading Stats");
+            let today = Local::now().date_naive();
+            let today_totals =
+                stats.days.get(&today).cloned().unwrap_or_default();
+            let daily_words = if stats.days.is_empty() {
+                0
+            } else {
+                stats.total.words / stats.days.len()
+            };
+            let rows = [
+                format!(
+                    " Total time   {:<12}Words  {:<12}Chapters  {}",
+                    history::format_duration(stats.total.seconds),
+                    stats.total.words,
+                    stats.total.chapters()
+                ),
+                format!(
+                    " Speed        {:<12}Streak {} days (longest {})",
+                    format!("{} wpm", stats.total.speed()),
+                    stats.current_streak,
+                    stats.longest_streak
+                ),
+                format!(
+                    " Today        {} chapters, {} words ({} words a day on average)",
+                    today_totals.chapters(), today_totals.words, daily_words
+                ),
+            ];
+            for (row, text) in rows.iter().enumerate() {
+                mvaddstr(3 + row as i32, 0, &fit(text, self.maxx as usize));
+            }
+            self.draw_heatmap(&stats, 7);
+
+            attron(A_BOLD);
+            mvaddstr(
+                17,
+                0,
+                &fit(
+                    &format!(
+                        "  {}  {:>8}  {:>9}  {:>8}  {:>7}",
+                        pad("Novel", (self.maxx as usize).saturating_sub(46)),
+                        "Chapters",
+                        "Words",
+                        "Time",
+                        "Speed"
+                    ),
+                    self.maxx as usize,
+                ),
+            );
+            attroff(A_BOLD);
+            list.draw(
+                18,
+                self.maxy - 19,
+                self.maxx,
+                |(novel, totals), width| {
+                    format!(
+                        "  {}  {:>8}  {:>9}  {:>8}  {:>7}",
+                        pad(novel, width.saturating_sub(46)),
+                        totals.chapters(),
+                        totals.words,
+                        history::format_duration(totals.seconds),
+                        format!("{} wpm", totals.speed())
+                    )
+                },
+            );
+            if stats.novels.is_empty() {
+                mvaddstr(18, 0, " Nothing read yet");
+            }
+            let status = if message.is_empty() {
+                " (e) export to csv  (q) back".to_string()
+            } else {
+                message.clone()
+            };
+            mvaddstr(self.maxy, 0, &status);
+            refresh();
+
+            let ch = getch();
+            message.clear();
+            match ch {
+                // q
+                113 => {
                     clear();
-                    noecho();
-                    keypad(stdscr(), false);
-                    addstr("\n");
-                    let prompt =
-                        format!(" enter chapter [1 - {}]: ", max_chapter);
-                    x = prompt.len() as i32;
-                    prompt_len = prompt.len() as i32 + 1;
-                    addstr(prompt.as_str());
-                    let mut novel: Vec<chtype> = Vec::new();
-                    ch = getch();
-                    loop {
-                        match ch {
-                            10 => {
-                                mvinchnstr(1, 0, &mut novel, self.maxx + 1);
-                                let chapter = novel
-                                    .iter()
-                                    .map(|x| char::from_u32(x.clone()).unwrap())
-                                    .collect::<String>();
-                                let mut chapter = chapter
-                                    .split(":")
-                                    .collect::<Vec<_>>()
-                                    .pop()
-                                    .unwrap()
-                                    .to_string();
-                                chapter.pop();
-                                chapter = chapter.trim().to_string();
-
-                                match chapter.parse::<i32>() {
-                                    Ok(val) => {
-                                        if val <= 0 {
-                                            chapter = "1".to_string();
-                                        } else if val >= max_chapter {
-                                            chapter = max_chapter.to_string();
-                                        }
-                                        self.change_chapter(
-                                            chapter.parse::<i32>().unwrap()
-                                                - max_chapter,
-                                        );
-                                        break;
-                                    }
-                                    Err(_) => {
-                                        clear();
-                                        addstr("\n");
-                                        let prompt = format!(
-                                            " enter correct chapter [1 - {}]: ",
-                                            max_chapter
-                                        );
-                                        x = prompt.len() as i32;
-                                        prompt_len = prompt.len() as i32 + 1;
-                                        addstr(prompt.as_str());
-                                        ch = getch();
-                                    }
-                                }
-                            }
-                            _ => {
-                                if ch == 127 {
-                                    if x < prompt_len {
-                                        x = prompt_len;
-                                    }
-                                    mvdelch(1, x - 1);
-                                    x -= 1;
-                                } else {
-                                    x += 1;
-                                    addch(ch as u32);
+                    break;
+                }
+                // e
+                101 => {
+                    let default = self.paths.data("lightnovel-history.csv");
+                    let out_path = self
+                        .prompt_with(" export to: ", &default)
+                        .map(|x| x.trim().to_string())
+                        .filter(|x| !x.is_empty());
+                    message = match out_path {
+                        Some(out_path) => {
+                            match history::export_csv(&records, &out_path) {
+                                Ok(count) => format!(
+                                    " exported {} chapters to {}",
+                                    count, out_path
+                                ),
+                                Err(err) => {
+                                    format!(" error exporting history: {}", err)
                                 }
-                                ch = getch();
                             }
                         }
+                        None => String::new(),
+                    };
+                }
+                _ => {
+                    list.handle_key(ch, self.maxy - 19);
+                }
+            }
+        }
+    }
+
+    // one column per week and one row per weekday starting at row y, darker
+    // cells for days with more words read
+    fn draw_heatmap(&self, stats: &Stats, y: i32) {
+        const SHADES: [&str; 5] = ["·", "░", "▒", "▓", "█"];
+        let weeks = ((self.maxx - 6) / 2).clamp(1, 53) as i64;
+        let today = Local::now().date_naive();
+        let monday = today
+            - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let start = monday - Duration::weeks(weeks - 1);
+        let most = stats.days.values().map(|x| x.words).max().unwrap_or(0);
+
+        for (row, name) in
+            ["Mon", "", "Wed", "", "Fri", "", ""].iter().enumerate()
+        {
+            mvaddstr(y + 1 + row as i32, 1, name);
+        }
+        for week in 0..weeks {
+            let x = 5 + week as i32 * 2;
+            let first = start + Duration::weeks(week);
+            // month names over the first week of each month
+            if first.day() <= 7 && x + 3 < self.maxx {
+                mvaddstr(y, x, &first.format("%b").to_string());
+            }
+            for weekday in 0..7 {
+                let day = first + Duration::days(weekday);
+                if day > today {
+                    break;
+                }
+                let words = stats.days.get(&day).map_or(0, |x| x.words);
+                let level = if words == 0 || most == 0 {
+                    0
+                } else {
+                    1 + (words * 3 / most).min(3)
+                };
+                let attr = if level == 0 { A_DIM } else { COLOR_PAIR(1) };
+                attron(attr);
+                mvaddstr(y + 1 + weekday as i32, x, SHADES[level]);
+                attroff(attr);
+            }
+        }
+    }
+
+    // narrow the list as the filter is typed, up/down move the highlighted
+    // novel and enter returns its url
+    fn filter_welcome_screen(&mut self) -> Option<String> {
+        let mut editor = LineEditor::new("");
+        let mut list = ListView::new(vec![]);
+        loop {
+            let filter = editor.text();
+            let (selected, offset) = (list.selected, list.offset);
+            list = ListView::new(self.make_welcome_screen(&filter));
+            list.select(selected);
+            list.offset = offset;
+
+            clear();
+            self.draw_welcome_list(&mut list, &filter);
+            mvaddstr(self.maxy, 0, "/");
+            editor.draw(self.maxy, 1, self.maxx - 2);
+
+            let key = match get_wch() {
+                Some(key) => key,
+                None => continue,
+            };
+            match key {
+                WchResult::KeyCode(KEY_DOWN) | WchResult::Char(14) => {
+                    list.select(list.selected + 1);
+                }
+                WchResult::KeyCode(KEY_UP) | WchResult::Char(16) => {
+                    list.select(list.selected.saturating_sub(1));
+                }
+                key => match editor.handle_key(key) {
+                    Action::Continue => list.select(0),
+                    Action::Submit => {
+                        return list.selected_item().map(|x| x.url.clone());
                     }
-                    noecho();
-                    self.get_doc().await?;
-                    self.draw_chapter_screen().await?;
+                    Action::Cancel => return None,
+                },
+            }
+        }
+    }
+
+    pub async fn display_search_screen(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        clear();
+        self.draw_search_header();
+
+        // complete from past searches, newest first, then the library
+        let history = search_history::load(&self.search_history_path());
+        let mut completions = history.iter().rev().cloned().collect::<Vec<_>>();
+        completions.extend(self.library_titles());
+        let mut editor = LineEditor::new("")
+            .with_history(history)
+            .with_completions(completions);
+
+        let keyword = match editor.read(3, 3, self.maxx - 4) {
+            Some(keyword) => keyword.trim().to_string(),
+            None => {
+                clear();
+                return Ok(());
+            }
+        };
+        self.report(search_history::add(&self.search_history_path(), &keyword));
+        let (page, empty) = match search::search(&keyword).await {
+            Ok(page) if keyword.len() < 3 => {
+                (page, " Please enter more than 3 charachters!".to_string())
+            }
+            Ok(page) => (page, " No Light Novels Found!".to_string()),
+            Err(err) => {
+                (SearchPage::default(), format!(" Search failed: {}", err))
+            }
+        };
+        self.display_results(
+            " Search for LightNovels:",
+            &format!(" > {}", keyword),
+            &empty,
+            page,
+        )
+        .await
+    }
+
+    // pages of novels from a search or a catalog, enter shows the details
+    // of the highlighted one
+    async fn display_results(
+        &mut self,
+        header: &str,
+        subtitle: &str,
+        empty: &str,
+        page: SearchPage,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut list = ListView::new(page.results);
+        let (mut next, mut prev) = (page.next, page.prev);
+        let mut page_number = 1;
+        // shown instead of the page number until the next key
+        let mut message = String::new();
+        curs_set(false);
+
+        loop {
+            clear();
+            draw_header(header);
+            mvaddstr(3, 0, subtitle);
+            if list.items.is_empty() {
+                mvaddstr(5, 0, empty);
+                mvaddstr(7, 0, " Press (q) to go back");
+            }
+
+            // preview on the right if there is room, below the list if not
+            let wide = self.maxx >= 100;
+            let (list_width, list_height) = if wide {
+                (self.maxx * 3 / 5, self.maxy - 6)
+            } else {
+                (self.maxx, self.maxy - 14)
+            };
+            list.draw(5, list_height, list_width, search_row);
+            if let Some(novel) = list.selected_item() {
+                if wide {
+                    self.draw_preview(
+                        novel,
+                        5,
+                        list_width + 1,
+                        list_height,
+                        self.maxx - list_width - 1,
+                    );
+                } else {
+                    self.draw_preview(novel, self.maxy - 8, 0, 7, self.maxx);
+                }
+            }
+
+            let mut status = format!(" page {}", page_number);
+            if next.is_some() {
+                status.push_str("  (n) next");
+            }
+            if prev.is_some() {
+                status.push_str("  (p) previous");
+            }
+            if !message.is_empty() {
+                status = std::mem::take(&mut message);
+            }
+            mvaddstr(self.maxy - 1, 0, &status);
+            refresh();
+
+            // give the selection a moment to settle before fetching the
+            // synopsis, so scrolling through results stays fast
+            let pending =
+                list.selected_item().is_some_and(|x| x.synopsis.is_none());
+            timeout(if pending { 300 } else { -1 });
+            let ch = getch();
+            timeout(-1);
+            match ch {
+                ERR => {
+                    let selected = list.selected;
+                    let url = list.items[selected].url.clone();
+                    let synopsis = match novel::fetch(&url).await {
+                        Ok(x) if !x.synopsis.is_empty() => x.synopsis,
+                        Ok(_) => "No synopsis available.".to_string(),
+                        Err(err) => {
+                            format!("Couldn't load the synopsis: {}", err)
+                        }
+                    };
+                    list.items[selected].synopsis = Some(synopsis);
+                }
+                // q
+                113 => {
+                    clear();
                     break;
                 }
+                // n or p
+                110 | 112 => {
+                    let url = match (ch, &next, &prev) {
+                        (110, Some(url), _) | (112, _, Some(url)) => {
+                            url.clone()
+                        }
+                        _ => continue,
+                    };
+                    mvaddstr(self.maxy - 1, 0, " loading...");
+                    clrtoeol();
+                    refresh();
+                    let page = match search::fetch_page(&url).await {
+                        Ok(page) => page,
+                        Err(err) => {
+                            message =
+                                format!(" couldn't load the page: {}", err);
+                            continue;
+                        }
+                    };
+                    list = ListView::new(page.results);
+                    next = page.next;
+                    prev = page.prev;
+                    page_number += if ch == 110 { 1 } else { -1 };
+                }
+                10 => {
+                    let url = match list.selected_item() {
+                        Some(novel) if !novel.url.is_empty() => {
+                            novel.url.clone()
+                        }
+                        _ => continue,
+                    };
+                    self.display_details_screen(&url).await?;
+                }
                 _ => {
-                    ch = getch();
+                    list.handle_key(ch, list_height);
                 }
             }
         }
         Ok(())
     }
 
-    fn change_chapter(&mut self, offset: i32) {
-        let result = self.url.clone();
-        // split url into parts to get number and update it
-        let mut result = result.split("/").collect::<Vec<_>>();
-        let mut chapter_string = result[4].split("-").collect::<Vec<_>>();
-        let mut chapter = chapter_string[1].split(".").collect::<Vec<_>>();
-        let mut chapter_number = chapter[0].parse::<i32>().unwrap();
-        chapter_number += offset;
-        // combine the lists to 1 string and store it in self.url
-        let chapter_number = chapter_number.to_string();
-        chapter[0] = chapter_number.as_str();
-        let chapter = chapter.join(".");
-        chapter_string[1] = chapter.as_str();
-        let chapter_string = chapter_string.join("-");
-        result[4] = chapter_string.as_str();
-        let result = result.join("/");
-        self.url = result;
-    }
-
-    fn make_welcome_screen(&mut self) -> (Vec<String>, Vec<String>) {
-        let mut result: Vec<String> = Vec::new();
-        result.push("\n".to_string());
-        result.push(" Recently Read Novels\n".to_string());
-        result.push("\n".to_string());
-
-        // read previously read light novels from text file
-        let mut line: String = String::new();
-        let mut chapter_urls = Vec::new();
-        let file_buffer = File::options()
-            .read(true)
-            .open(&self.path)
-            .expect("error reading config file");
-
-        let mut reader = BufReader::new(file_buffer);
-        loop {
-            match reader.read_line(&mut line) {
-                Ok(val) => {
-                    if val != 0 {
-                        chapter_urls.push(
-                            line.split("#").collect::<Vec<&str>>()[1]
-                                .to_string(),
-                        );
-                        let mut content =
-                            line.split("#").collect::<Vec<&str>>()[0]
-                                .to_string()
-                                .trim()
-                                .to_string();
-                        if content.len() as i32 >= self.maxx - 3 {
-                            let mut temp = content.clone();
-                            temp = temp
-                                .chars()
-                                .take(self.maxx as usize - 10)
-                                .collect();
-                            temp.push_str("...");
-                            content = temp;
-                        }
-                        line = format!("  *  {}", content);
-                        line.push_str("\n");
-                        result.push(line);
-                        line = String::new();
-                    } else {
-                        break;
+    // title, author, status and synopsis of a search result in the given
+    // area
+    fn draw_preview(
+        &self,
+        novel: &SearchResult,
+        y: i32,
+        x: i32,
+        height: i32,
+        width: i32,
+    ) {
+        let width = width.max(1);
+        let mut lines = vec![
+            (novel.title.clone(), A_BOLD | COLOR_PAIR(1)),
+            (format!("by {}", novel.author), A_DIM),
+            (
+                format!("{} chapters  {}", novel.chapters, novel.status),
+                A_DIM,
+            ),
+            (String::new(), 0),
+        ];
+        match &novel.synopsis {
+            Some(synopsis) => {
+                for paragraph in synopsis.lines() {
+                    let block = Block::Paragraph(vec![Span {
+                        text: paragraph.to_string(),
+                        style: Style::default(),
+                    }]);
+                    for line in layout::layout_block(&block, width - 1, 0) {
+                        lines.push((line.text.trim_end().to_string(), 0));
                     }
                 }
-                Err(err) => panic!("error read config file {}", err),
             }
+            None => lines.push(("loading synopsis...".to_string(), A_DIM)),
+        }
+
+        for (row, (text, attr)) in
+            lines.iter().take(height as usize).enumerate()
+        {
+            attron(*attr);
+            mvaddstr(y + row as i32, x + 1, &fit(text, width as usize - 1));
+            attroff(*attr);
         }
-        self.doc = result.clone();
-        (result, chapter_urls)
     }
 
-    async fn make_search_screen(
+    // cover, synopsis and our progress of a novel, with actions to start,
+    // resume, add it to the library or download it
+    pub async fn display_details_screen(
         &mut self,
-        keyword: Option<String>,
-    ) -> Result<Option<Vec<[String; 3]>>, Box<dyn std::error::Error>> {
-        let mut search_result = Vec::new();
-        match keyword {
-            Some(mut keyword) => {
-                // trim keyword
-                keyword.pop();
-                keyword = keyword.trim().to_string();
-                let url = "https://freewebnovel.com/search/";
-                // pass a post request to get a response containing results
-                let params = [("searchkey", keyword.as_str())];
-                let client = reqwest::Client::new();
-                let resp = match client.post(url).form(&params).send().await {
-                    Ok(x) => x.text().await?,
-                    Err(_) => panic!("connection refused!"),
-                };
-                let fragment = Html::parse_fragment(&resp);
-
-                // parse the document for data
-                let title_div = Selector::parse(r#"div[class="txt"]"#).unwrap();
-                let url_selector = Selector::parse(r#"h3"#).unwrap();
-                let title_selector = Selector::parse(r#"a"#).unwrap();
-                for element in fragment.select(&title_div) {
-                    let title = element.select(&url_selector).next().unwrap();
-                    let chapter_number =
-                        Selector::parse(r#"a[class="chapter"]"#).unwrap();
-                    let mut novel_data: [String; 3] =
-                        ["".to_string(), "".to_string(), "".to_string()];
-                    match title.select(&title_selector).next() {
-                        Some(c) => {
-                            let mut content =
-                                c.value().attr("title").unwrap().to_string();
-                            if content.len() as i32 >= self.maxx - 9 {
-                                let mut temp = content.clone();
-                                temp = temp
-                                    .chars()
-                                    .take(self.maxx as usize - 9)
-                                    .collect();
-                                temp.push_str("...");
-                                content = temp;
-                            }
-                            novel_data[0] = content;
-                        }
-                        _ => {}
+        url: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        clear();
+        mvaddstr(1, 0, " loading...");
+        refresh();
+        let info = match novel::fetch(url).await {
+            Ok(info) => info,
+            Err(err) => {
+                mvaddstr(1, 0, &format!(" Couldn't load the novel: {}", err));
+                clrtoeol();
+                mvaddstr(3, 0, " Press any key to go back");
+                getch();
+                clear();
+                return Ok(());
+            }
+        };
+        let slug = url
+            .split('/')
+            .nth(3)
+            .unwrap_or("")
+            .trim_end_matches(".html");
+        let cover = if info.cover_url.is_empty() || self.maxx < 70 {
+            None
+        } else {
+            match cover::fetch(&info.cover_url).await {
+                Ok(bytes) => {
+                    cover::text_art(&bytes, 24, (self.maxy - 6).max(1) as u32)
+                }
+                Err(_) => None,
+            }
+        };
+        let mut message = String::new();
+        curs_set(false);
+        // keep the chapter count of novels in the library up to date for
+        // sorting by unread chapters and progress
+        if info.chapters > 0 {
+            self.report(library::update(
+                &self.path,
+                &slug.replace('-', " "),
+                |x| {
+                    x.chapters = info.chapters;
+                    x.name = info.title.clone();
+                },
+            ));
+        }
+
+        loop {
+            // reading from here moves the library entry, so look it up again
+            let saved = self
+                .library()
+                .into_iter()
+                .find(|x| x.title == slug.replace('-', " "))
+                .map(|x| x.url);
+            let downloaded = cache::count(&self.cache_dir(), slug);
+            clear();
+            self.draw_details(&info, &cover, &saved, downloaded, &message);
+            refresh();
+
+            let ch = getch();
+            message.clear();
+            match ch {
+                // q
+                113 => {
+                    clear();
+                    break;
+                }
+                // s
+                115 if info.chapters > 0 => {
+                    if let Some(url) = novel::chapter_url(&info, 1) {
+                        self.url = url;
+                        self.get_doc().await?;
+                        self.draw_chapter_screen().await?;
                     }
-                    match element.select(&chapter_number).next() {
-                        Some(c) => {
-                            // add all data in a vector
-                            let link = c.value().attr("href");
-                            let chapter_number =
-                                Selector::parse(r#"span[class="s1"]"#).unwrap();
-                            let chapter = c
-                                .select(&chapter_number)
-                                .next()
-                                .unwrap()
-                                .text()
-                                .collect::<Vec<_>>()[0]
-                                .split(" ")
-                                .collect::<Vec<_>>()[0];
-                            novel_data[1] = link.unwrap().to_string();
-                            novel_data[2] = chapter.to_string();
-                        }
-                        _ => {}
+                }
+                // r
+                114 => {
+                    if let Some(url) = saved {
+                        self.url = url;
+                        self.get_doc().await?;
+                        self.draw_chapter_screen().await?;
                     }
-                    search_result.push(novel_data);
-                }
-                // result contains the data to be printed on the screen
-                let mut result = Vec::new();
-                result.push("\n".to_string());
-                result.push(" Search for LightNovel\n".to_string());
-                result.push("\n".to_string());
-                result.push(" > \n".to_string());
-                if search_result.len() >= 1 {
-                    for value in search_result.iter() {
-                        let mut line = format!("  *  {}", value[0]);
-                        line.push_str("\n");
-                        result.push(line);
+                }
+                // c
+                99 if info.chapters > 0 => {
+                    if let Some(url) = self
+                        .read_chapter_number(info.chapters)
+                        .and_then(|x| novel::chapter_url(&info, x))
+                    {
+                        self.url = url;
+                        self.get_doc().await?;
+                        self.draw_chapter_screen().await?;
                     }
-                } else {
-                    if keyword.len() < 3 {
-                        result.push(
-                            " Please enter more than 3 charachters!"
-                                .to_string(),
-                        );
-                        result.push("\n".to_string());
-                        result.push(" Press (q) to go back".to_string());
+                }
+                // a
+                97 if saved.is_none() && info.chapters > 0 => {
+                    let first = match novel::chapter_url(&info, 1) {
+                        Some(url) => url,
+                        None => continue,
+                    };
+                    let title =
+                        library::title_from_url(&first).unwrap_or_default();
+                    let result =
+                        library::add(&self.path, &first, 0).and_then(|_| {
+                            library::update(&self.path, &title, |x| {
+                                x.status = Status::PlanToRead;
+                                x.chapters = info.chapters;
+                                x.seen = info.chapters;
+                                x.name = info.title.clone();
+                            })
+                        });
+                    message = match result {
+                        Ok(()) => " added to plan to read".to_string(),
+                        Err(err) => format!(" couldn't save: {}", err),
+                    };
+                }
+                // d
+                100 if info.chapters > 0 => {
+                    message = self.download(&info).await;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_details(
+        &self,
+        info: &NovelInfo,
+        cover: &Option<Vec<String>>,
+        saved: &Option<String>,
+        downloaded: usize,
+        message: &str,
+    ) {
+        attron(A_BOLD);
+        attron(COLOR_PAIR(1));
+        mvaddstr(1, 0, &fit(&format!(" {}", info.title), self.maxx as usize));
+        attroff(COLOR_PAIR(1));
+        attroff(A_BOLD);
+
+        // cover on the left, everything else next to it
+        let mut x = 1;
+        if let Some(cover) = cover {
+            for (row, line) in cover.iter().enumerate() {
+                mvaddstr(3 + row as i32, 1, line);
+            }
+            x = 27;
+        }
+        let width = (self.maxx - x - 1).max(1) as usize;
+
+        let progress = match saved.as_deref().and_then(novel::chapter_number) {
+            Some(chapter) if info.chapters > 0 => format!(
+                "chapter {} of {} ({}%)",
+                chapter,
+                info.chapters,
+                chapter * 100 / info.chapters
+            ),
+            Some(chapter) => format!("chapter {}", chapter),
+            None => "not in library".to_string(),
+        };
+        let rows = [
+            ("Author", info.author.clone()),
+            ("Genres", info.genres.join(", ")),
+            ("Status", info.status.clone()),
+            ("Chapters", info.chapters.to_string()),
+            ("Downloaded", downloaded.to_string()),
+            ("Progress", progress),
+        ];
+        let mut y = 3;
+        for (label, value) in rows.iter() {
+            attron(A_BOLD);
+            mvaddstr(y, x, &format!("{:<12}", label));
+            attroff(A_BOLD);
+            addstr(&fit(value, width.saturating_sub(12)));
+            y += 1;
+        }
+
+        y += 1;
+        for paragraph in info.synopsis.lines() {
+            let block = Block::Paragraph(vec![Span {
+                text: paragraph.to_string(),
+                style: Style::default(),
+            }]);
+            for line in layout::layout_block(&block, width as i32, 0) {
+                if y >= self.maxy - 2 {
+                    break;
+                }
+                mvaddstr(y, x, &fit(line.text.trim_end(), width));
+                y += 1;
+            }
+        }
+
+        let mut actions = " (s) start".to_string();
+        if saved.is_some() {
+            actions.push_str("  (r) resume");
+        }
+        actions.push_str("  (c) choose chapter");
+        if saved.is_none() {
+            actions.push_str("  (a) add to library");
+        }
+        actions.push_str("  (d) download  (q) back");
+        mvaddstr(self.maxy - 2, 0, message);
+        mvaddstr(self.maxy - 1, 0, &fit(&actions, self.maxx as usize));
+    }
+
+    // save every chapter of a novel to the cache so it can be read
+    // offline, any key stops the download
+    async fn download(&self, info: &NovelInfo) -> String {
+        let dir = self.cache_dir();
+        let mut done = 0;
+        timeout(0);
+        for chapter in 1..=info.chapters {
+            if getch() != ERR {
+                timeout(-1);
+                return format!(" download stopped at chapter {}", chapter);
+            }
+            let url = match novel::chapter_url(info, chapter) {
+                Some(url) => url,
+                None => break,
+            };
+            if cache::contains(&dir, &url) {
+                continue;
+            }
+            self.draw_status(&format!(
+                " downloading chapter {} of {}, press any key to stop",
+                chapter, info.chapters
+            ));
+            refresh();
+            let html = match chapter::fetch_html(&url).await {
+                Ok(html) => html,
+                Err(_) => continue,
+            };
+            // missing chapters aren't worth keeping
+            if chapter::parse(&html).is_none() {
+                continue;
+            }
+            if let Err(err) = cache::store(&dir, &url, &html) {
+                timeout(-1);
+                return format!(" error saving chapter {}: {}", chapter, err);
+            }
+            done += 1;
+        }
+        timeout(-1);
+        format!(" downloaded {} chapters", done)
+    }
+
+    fn draw_search_header(&self) {
+        draw_header(" Search for LightNovels:");
+        mvaddstr(3, 0, " > ");
+    }
+
+    // every setting of config.toml, enter toggles, cycles or edits the
+    // highlighted one and saves it right away
+    pub fn display_settings_screen(&mut self) {
+        let mut list = ListView::new(config::entries(&self.config));
+        let mut message = String::new();
+        curs_set(false);
+
+        loop {
+            clear();
+            draw_header(" Settings:");
+            list.draw(3, self.maxy - 4, self.maxx, |(key, value), width| {
+                format!(
+                    "  {}{}",
+                    pad(key, 28),
+                    fit(&config::format_value(value), width.saturating_sub(30))
+                )
+            });
+            mvaddstr(self.maxy - 1, 0, &fit(&message, self.maxx as usize));
+            refresh();
+
+            let ch = getch();
+            message.clear();
+            match ch {
+                // q
+                113 => {
+                    clear();
+                    break;
+                }
+                10 => {
+                    let (key, value) = match list.selected_item() {
+                        Some(entry) => entry.clone(),
+                        None => continue,
+                    };
+                    let text = config::format_value(&value);
+                    let new = if let toml::Value::Boolean(value) = value {
+                        Some((!value).to_string())
+                    } else if let Some(choices) = config::choices(&key) {
+                        let index = choices.iter().position(|x| *x == text);
+                        let next = index.map(|x| x + 1).unwrap_or(0);
+                        Some(choices[next % choices.len()].to_string())
                     } else {
-                        result.push(" No Light Novels Found!".to_string());
-                        result.push("\n".to_string());
-                        result.push(" Press (q) to go back".to_string());
+                        self.prompt_with(&format!(" {}: ", key), &text)
+                    };
+                    let new = match new {
+                        Some(new) => new,
+                        None => continue,
+                    };
+                    if let Err(err) = config::set(&mut self.config, &key, &new)
+                    {
+                        message = format!(" {}", err);
+                        continue;
+                    }
+                    if let Err(err) = config::save_setting(
+                        &self.config_path(),
+                        &key,
+                        &self.config,
+                    ) {
+                        message = format!(" couldn't save settings: {}", err);
+                    }
+                    if let Err(err) = self.apply_config() {
+                        message = format!(" {}", err);
                     }
+                    let selected = list.selected;
+                    list = ListView::new(config::entries(&self.config));
+                    list.select(selected);
+                }
+                _ => {
+                    list.handle_key(ch, self.maxy - 4);
                 }
-                self.doc = result;
-                Ok(Some(search_result))
             }
-            None => {
-                let mut result = Vec::new();
-                result.push("\n".to_string());
-                result.push(" Search for LightNovels: \n".to_string());
-                result.push("\n".to_string());
-                result.push(" > ".to_string());
-                result.push("\n".to_string());
-                for (i, value) in search_result.into_iter().enumerate() {
-                    let line = format!(" [{}] {}", i, value[0]);
-                    result.push(line);
+        }
+    }
+
+    // make the settings take effect without a restart, the reading width
+    // applies from the next chapter that is opened
+    fn apply_config(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.continuous = self.config.reader.continuous;
+        if self.config.cache.max_mb > 0 {
+            cache::prune(&self.cache_dir(), self.config.cache.max_mb << 20);
+        }
+        net::configure(&self.config)
+    }
+
+    // latest releases, most popular, completed and genre listings
+    pub async fn display_browse_screen(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut list = ListView::new(search::catalogs());
+        let mut message = String::new();
+        curs_set(false);
+
+        loop {
+            clear();
+            draw_header(" Browse LightNovels:");
+            list.draw(3, self.maxy - 3, self.maxx, |(name, _), _| {
+                format!("  *  {}", name)
+            });
+            self.draw_status(&std::mem::take(&mut message));
+
+            let ch = getch();
+            match ch {
+                // q
+                113 => {
+                    clear();
+                    break;
+                }
+                10 => {
+                    let (name, url) = match list.selected_item() {
+                        Some(catalog) => catalog.clone(),
+                        None => continue,
+                    };
+                    clear();
+                    mvaddstr(1, 0, " loading...");
+                    refresh();
+                    let page = match search::fetch_page(&url).await {
+                        Ok(page) => page,
+                        Err(err) => {
+                            message =
+                                format!(" couldn't load {}: {}", name, err);
+                            continue;
+                        }
+                    };
+                    self.display_results(
+                        " Browse LightNovels:",
+                        &format!(" {}", name),
+                        " No Light Novels Found!",
+                        page,
+                    )
+                    .await?;
+                }
+                _ => {
+                    list.handle_key(ch, self.maxy - 3);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // ask for a chapter between 1 and max_chapter, None if cancelled
+    fn read_chapter_number(&self, max_chapter: i32) -> Option<i32> {
+        let mut prompt = format!(" enter chapter [1 - {}]: ", max_chapter);
+        loop {
+            clear();
+            mvaddstr(1, 0, &prompt);
+            let width = self.maxx - prompt.len() as i32 - 1;
+            let chapter =
+                LineEditor::new("").read(1, prompt.len() as i32, width)?;
+            match chapter.trim().parse::<i32>() {
+                Ok(val) => return Some(val.max(1).min(max_chapter)),
+                Err(_) => {
+                    prompt = format!(
+                        " enter correct chapter [1 - {}]: ",
+                        max_chapter
+                    )
                 }
-                self.doc = result;
-                Ok(None)
             }
         }
     }
+
+    fn change_chapter(&mut self, offset: i32) {
+        if let Some(url) = novel::offset_chapter_url(&self.url, offset) {
+            self.url = url;
+        }
+    }
+
+    // novels in the library on the current shelf, only the ones fuzzy
+    // matching filter (best match first) if it isn't empty
+    fn make_welcome_screen(&self, filter: &str) -> Vec<Entry> {
+        let mut novels = self
+            .library()
+            .into_iter()
+            .filter(|x| self.shelf.is_none_or(|status| x.status == status))
+            .filter(|x| match &self.shelf_tag {
+                Some(tag) => x.tags.contains(tag),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+
+        if filter.is_empty() {
+            library::sort(&mut novels, Sort::parse(&self.config.library.sort));
+            return novels;
+        }
+        let titles = novels
+            .iter()
+            .map(|x| x.display_title().to_string())
+            .collect::<Vec<_>>();
+        fuzzy::rank(filter, &titles)
+            .into_iter()
+            .map(|x| novels[x].clone())
+            .collect()
+    }
+}
+
+fn draw_header(text: &str) {
+    attron(A_BOLD);
+    attron(COLOR_PAIR(1));
+    mvaddstr(1, 0, text);
+    attroff(COLOR_PAIR(1));
+    attroff(A_BOLD);
+}
+
+// a library row, columns are dropped from the right as the screen narrows:
+// title and tags, chapter, progress bar, new chapters, last read, status
+fn library_row(novel: &Entry, width: usize) -> String {
+    let chapter = if novel.chapters > 0 {
+        format!("{}/{}", novel.chapter(), novel.chapters)
+    } else {
+        novel.chapter().to_string()
+    };
+    let mut columns = format!("  {:>9}", chapter);
+    if width >= 70 {
+        columns.push_str(&format!("  {}", progress_bar(novel.progress(), 12)));
+    }
+    let new = match novel.new_chapters() {
+        0 => String::new(),
+        count => format!("+{} new", count),
+    };
+    columns.push_str(&format!("  {}", pad(&new, 9)));
+    if width >= 95 {
+        let last_read = library::relative_time(novel.last_read);
+        columns.push_str(&format!("  {:>8}", last_read));
+    }
+    if width >= 115 {
+        columns.push_str(&format!("  {}", pad(novel.status.label(), 12)));
+    }
+
+    let mut title = novel.display_title().to_string();
+    for tag in novel.tags.iter() {
+        title.push_str(&format!(" #{}", tag));
+    }
+    let title_width = width.saturating_sub(columns.len() + 5);
+    format!("  *  {}{}", pad(&title, title_width), columns)
+}
+
+// "[#####-------] 42%" style bar, blank if the progress isn't known
+fn progress_bar(progress: Option<i32>, width: usize) -> String {
+    let progress = match progress {
+        Some(progress) => progress,
+        None => return " ".repeat(width + 6),
+    };
+    let filled = width * progress as usize / 100;
+    format!(
+        "[{}{}]{:>3}%",
+        "#".repeat(filled),
+        "-".repeat(width - filled),
+        progress
+    )
+}
+
+// a search result row: title, author, latest chapter and status columns
+fn search_row(novel: &SearchResult, width: usize) -> String {
+    let chapters = format!("{:>5} ch", novel.chapters);
+    if width < 60 {
+        let title = width.saturating_sub(chapters.len() + 7);
+        return format!("  *  {} {}", pad(&novel.title, title), chapters);
+    }
+    let title = width.saturating_sub(20 + chapters.len() + 9 + 11);
+    format!(
+        "  *  {}  {}  {}  {}",
+        pad(&novel.title, title),
+        pad(&novel.author, 20),
+        chapters,
+        pad(&novel.status, 9)
+    )
+}
+
+// the mode, its settings and the keys that change them
+fn autoscroll_status(autoscroll: &AutoScroll) -> String {
+    let mut status = if autoscroll.paused {
+        "-- PAUSED --".to_string()
+    } else {
+        "-- AUTO SCROLL --".to_string()
+    };
+    status.push_str(&format!(
+        " every {:.1}s by {}",
+        autoscroll.interval.as_secs_f32(),
+        if autoscroll.by_paragraph {
+            "paragraph"
+        } else {
+            "line"
+        }
+    ));
+    status.push_str("  space: pause, +/-: speed, a: stop");
+    status
+}
+
+fn read_aloud_status(read_aloud: &ReadAloud) -> String {
+    let mut status = if read_aloud.paused {
+        "-- PAUSED --".to_string()
+    } else {
+        "-- READING ALOUD --".to_string()
+    };
+    status.push_str(&format!(" speed {}", read_aloud.speed));
+    if let Some(left) = read_aloud.sleep_left() {
+        status.push_str(&format!(" sleep in {}m", left.as_secs() / 60 + 1));
+    }
+    status.push_str("  space: pause, +/-: speed, t: sleep timer, r: stop");
+    status
+}
+
+fn style_attr(style: Style) -> attr_t {
+    let mut attr = 0;
+    // one color pair, a highlight wins over the heading and quote colors
+    if style.highlight {
+        attr |= COLOR_PAIR(2);
+    } else if style.heading {
+        attr |= COLOR_PAIR(1);
+    } else if style.quote {
+        attr |= COLOR_PAIR(4);
+    }
+    if style.heading {
+        attr |= A_BOLD;
+    }
+    if style.bold {
+        attr |= A_BOLD;
+    }
+    if style.italic {
+        attr |= A_ITALIC;
+    }
+    attr
 }
@@ -0,0 +1,313 @@
+//! searching the site and its listing and genre pages
+
+use scraper::{ElementRef, Html, Selector};
+
+use crate::net;
+
+// listing pages of the site, they use the same layout as search results
+const LISTINGS: [(&str, &str); 3] = [
+    ("Latest releases", "/latest-release-novels/"),
+    ("Most popular", "/most-popular-novels/"),
+    ("Completed", "/completed-novels/"),
+];
+
+const GENRES: [&str; 36] = [
+    "Action",
+    "Adult",
+    "Adventure",
+    "Comedy",
+    "Drama",
+    "Eastern",
+    "Ecchi",
+    "Fantasy",
+    "Game",
+    "Gender Bender",
+    "Harem",
+    "Historical",
+    "Horror",
+    "Josei",
+    "Martial Arts",
+    "Mature",
+    "Mecha",
+    "Mystery",
+    "Psychological",
+    "Reincarnation",
+    "Romance",
+    "School Life",
+    "Sci-fi",
+    "Seinen",
+    "Shoujo",
+    "Shounen",
+    "Shounen Ai",
+    "Slice of Life",
+    "Smut",
+    "Sports",
+    "Supernatural",
+    "Tragedy",
+    "Wuxia",
+    "Xianxia",
+    "Xuanhuan",
+    "Yaoi",
+];
+
+/// a novel on a search, listing or genre page
+#[derive(Clone)]
+pub struct SearchResult {
+    /// the title as the site writes it
+    pub title: String,
+    /// page of the novel, has the synopsis
+    pub url: String,
+    /// link to the latest chapter, used to work out other chapter urls
+    pub latest_url: String,
+    /// chapters released so far, 0 if not shown
+    pub chapters: i32,
+    /// who wrote it, may be empty
+    pub author: String,
+    /// "Ongoing", "Completed" or empty
+    pub status: String,
+    /// fetched when the result is first highlighted
+    pub synopsis: Option<String>,
+}
+
+/// one page of results
+#[derive(Default)]
+pub struct SearchPage {
+    /// the novels, in page order
+    pub results: Vec<SearchResult>,
+    /// link to the next page, None on the last
+    pub next: Option<String>,
+    /// link to the previous page, None on the first
+    pub prev: Option<String>,
+}
+
+/// first page of results for keyword
+pub async fn search(
+    keyword: &str,
+) -> Result<SearchPage, Box<dyn std::error::Error>> {
+    let url = format!("{}/search/", net::site());
+    // pass a post request to get a response containing results
+    let params = [("searchkey", keyword)];
+    let resp = net::post_form(&url, &params).await?.text().await?;
+    Ok(parse_page(&resp))
+}
+
+/// other pages are plain links taken from the pagination of a page
+pub async fn fetch_page(
+    url: &str,
+) -> Result<SearchPage, Box<dyn std::error::Error>> {
+    let resp = net::get(url).await?.text().await?;
+    Ok(parse_page(&resp))
+}
+
+/// (name, url) of every listing and genre page
+pub fn catalogs() -> Vec<(String, String)> {
+    let mut catalogs = LISTINGS
+        .iter()
+        .map(|(name, path)| {
+            (name.to_string(), format!("{}{}", net::site(), path))
+        })
+        .collect::<Vec<_>>();
+    for genre in GENRES {
+        catalogs.push((
+            format!("Genre: {}", genre),
+            format!("{}/genre/{}", net::site(), genre.replace(' ', "+")),
+        ));
+    }
+    catalogs
+}
+
+/// the results and pagination of a search, listing or genre page
+pub fn parse_page(html: &str) -> SearchPage {
+    let fragment = Html::parse_fragment(html);
+
+    let title_div = Selector::parse(r#"div[class="txt"]"#).unwrap();
+    let title_selector = Selector::parse("h3 a").unwrap();
+    let chapter_selector = Selector::parse(r#"a[class="chapter"]"#).unwrap();
+    let chapter_number = Selector::parse(r#"span[class="s1"]"#).unwrap();
+    let item_selector = Selector::parse(r#"div[class="item"]"#).unwrap();
+    let label_selector = Selector::parse("span[title]").unwrap();
+    let right_selector = Selector::parse(r#"div[class="right"]"#).unwrap();
+
+    let mut results = Vec::new();
+    for element in fragment.select(&title_div) {
+        let mut result = SearchResult {
+            title: String::new(),
+            url: String::new(),
+            latest_url: String::new(),
+            chapters: 0,
+            author: String::new(),
+            status: String::new(),
+            synopsis: None,
+        };
+        if let Some(c) = element.select(&title_selector).next() {
+            result.title = match c.value().attr("title") {
+                Some(title) => title.to_string(),
+                None => element_text(c),
+            };
+            result.url = absolute_url(c.value().attr("href").unwrap_or(""));
+        }
+        if let Some(c) = element.select(&chapter_selector).next() {
+            result.latest_url =
+                absolute_url(c.value().attr("href").unwrap_or(""));
+            // "123 Chapter 123"
+            if let Some(number) = c.select(&chapter_number).next() {
+                result.chapters = element_text(number)
+                    .split(' ')
+                    .next()
+                    .and_then(|x| x.parse::<i32>().ok())
+                    .unwrap_or(0);
+            }
+        }
+        // author, genre, chapter and status rows are told apart by the
+        // title of their icon
+        for item in element.select(&item_selector) {
+            let label = match item.select(&label_selector).next() {
+                Some(x) => x.value().attr("title").unwrap_or(""),
+                None => continue,
+            };
+            let value = match item.select(&right_selector).next() {
+                Some(x) => element_text(x),
+                None => continue,
+            };
+            match label {
+                "Author" => result.author = value,
+                "Status" => result.status = value,
+                _ => {}
+            }
+        }
+        if result.status.is_empty() {
+            let text = element_text(element);
+            if text.contains("Completed") {
+                result.status = "Completed".to_string();
+            } else if text.contains("Ongoing") {
+                result.status = "Ongoing".to_string();
+            }
+        }
+        if !result.title.is_empty() {
+            results.push(result);
+        }
+    }
+
+    // the site marks the pagination arrows with their text only, ">>" and
+    // "<<" jump to the last and first page and are left out
+    let page_selector =
+        Selector::parse("div.pages a, ul.pagination a").unwrap();
+    let mut next = None;
+    let mut prev = None;
+    for link in fragment.select(&page_selector) {
+        let href = match link.value().attr("href") {
+            Some(href) if !href.is_empty() && href != "#" => href,
+            _ => continue,
+        };
+        match element_text(link).to_lowercase().as_str() {
+            ">" | "›" | "next" | "next page" => {
+                next = Some(absolute_url(href))
+            }
+            "<" | "‹" | "prev" | "previous" | "previous page" => {
+                prev = Some(absolute_url(href))
+            }
+            _ => {}
+        }
+    }
+
+    SearchPage {
+        results,
+        next,
+        prev,
+    }
+}
+
+/// the text of an element, with whitespace collapsed
+pub fn element_text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// href made absolute, relative ones are on the site
+pub fn absolute_url(href: &str) -> String {
+    if href.starts_with("http") {
+        href.to_string()
+    } else if href.starts_with('/') {
+        format!("{}{}", net::site(), href)
+    } else {
+        format!("{}/{}", net::site(), href)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r##"
+<div class="ul-list1">
+  <div class="li-row">
+    <div class="txt">
+      <h3 class="tit"><a href="/shadow-slave.html" title="Shadow Slave">
+        Shadow Slave</a></h3>
+      <div class="desc">
+        <div class="item">
+          <span class="glyphicon glyphicon-user" title="Author"></span>
+          <div class="right">Guiltythree</div>
+        </div>
+        <div class="item">
+          <span class="glyphicon glyphicon-time" title="Status"></span>
+          <div class="right">OnGoing</div>
+        </div>
+        <div class="item">
+          <a href="/shadow-slave/chapter-2000.html" class="chapter">
+            <span class="s1">2000 Chapter 2000</span></a>
+        </div>
+      </div>
+    </div>
+  </div>
+</div>
+<div class="pages">
+  <ul>
+    <li><a href="/latest-release-novels/1">&lt;&lt;</a></li>
+    <li><a href="/latest-release-novels/2">&lt;</a></li>
+    <li><a href="/latest-release-novels/2">2</a></li>
+    <li><a class="cur" href="#">3</a></li>
+    <li><a href="/latest-release-novels/4">4</a></li>
+    <li><a href="/latest-release-novels/4">&gt;</a></li>
+    <li><a href="/latest-release-novels/187">&gt;&gt;</a></li>
+  </ul>
+</div>"##;
+
+    #[test]
+    fn parse_page_reads_results() {
+        let page = parse_page(PAGE);
+        assert_eq!(page.results.len(), 1);
+        let result = &page.results[0];
+        assert_eq!(result.title, "Shadow Slave");
+        assert_eq!(result.url, format!("{}/shadow-slave.html", net::site()));
+        assert_eq!(
+            result.latest_url,
+            format!("{}/shadow-slave/chapter-2000.html", net::site())
+        );
+        assert_eq!(result.chapters, 2000);
+        assert_eq!(result.author, "Guiltythree");
+        assert_eq!(result.status, "OnGoing");
+    }
+
+    #[test]
+    fn parse_page_follows_the_single_arrows() {
+        let page = parse_page(PAGE);
+        let url = |page| {
+            Some(format!("{}/latest-release-novels/{}", net::site(), page))
+        };
+        assert_eq!(page.next, url(4));
+        assert_eq!(page.prev, url(2));
+    }
+
+    #[test]
+    fn parse_page_without_pagination() {
+        let page = parse_page("<div class=\"txt\"></div>");
+        assert!(page.results.is_empty());
+        assert_eq!(page.next, None);
+        assert_eq!(page.prev, None);
+    }
+}
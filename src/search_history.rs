@@ -0,0 +1,38 @@
+//! past search queries, kept in `search_history.txt`
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+};
+
+// keep this many of the most recent searches
+const MAX_ENTRIES: usize = 100;
+
+/// past queries, oldest first
+pub fn load(path: &str) -> Vec<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => content
+            .lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| x.to_string())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// move the query to the end of the history, dropping the oldest entries
+pub fn add(path: &str, query: &str) -> io::Result<()> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(());
+    }
+    let mut history = load(path);
+    history.retain(|x| x != query);
+    history.push(query.to_string());
+    if history.len() > MAX_ENTRIES {
+        history.drain(..history.len() - MAX_ENTRIES);
+    }
+
+    let mut file_buffer = File::create(path)?;
+    write!(&mut file_buffer, "{}", history.join("\n"))
+}
@@ -0,0 +1,147 @@
+//! reading paragraphs aloud with an external speech program
+
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::{
+    io::Write,
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::config::TtsConfig;
+
+/// runs the configured program, one paragraph at a time
+pub struct Speaker {
+    command: String,
+    args: Vec<String>,
+    child: Option<Child>,
+}
+
+impl Speaker {
+    /// with the command and arguments of config, silent at first
+    pub fn new(config: &TtsConfig) -> Self {
+        Self {
+            command: config.command.clone(),
+            args: config.args.clone(),
+            child: None,
+        }
+    }
+
+    /// start speaking text in the background, stopping anything still playing
+    pub fn speak(&mut self, text: &str, speed: u32) -> std::io::Result<()> {
+        self.stop();
+        let args = self
+            .args
+            .iter()
+            .map(|x| x.replace("{speed}", &speed.to_string()))
+            .collect::<Vec<_>>();
+        let mut command = Command::new(&self.command);
+        command
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // a group of its own so stop also ends what a shell command started
+        #[cfg(unix)]
+        command.process_group(0);
+        let mut child = command.spawn()?;
+        // written from a thread so a long paragraph can't block the reader
+        // while the engine reads it, dropping stdin then closes it so the
+        // engine knows the text ended
+        if let Some(mut stdin) = child.stdin.take() {
+            let text = text.to_string();
+            thread::spawn(move || {
+                let _ = stdin.write_all(text.as_bytes());
+            });
+        }
+        self.child = Some(child);
+        Ok(())
+    }
+
+    /// true while the last paragraph is still being spoken
+    pub fn is_speaking(&mut self) -> bool {
+        match &mut self.child {
+            Some(child) => matches!(child.try_wait(), Ok(None)),
+            None => false,
+        }
+    }
+
+    /// stop speaking right away
+    pub fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            #[cfg(unix)]
+            unsafe {
+                libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for Speaker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+const SLEEP_TIMERS: [u64; 4] = [0, 15, 30, 60];
+
+/// reading a chapter aloud a paragraph at a time
+pub struct ReadAloud {
+    /// speaks the paragraphs
+    pub speaker: Speaker,
+    /// paragraph of raw_doc being spoken
+    pub paragraph: usize,
+    /// false until paragraph is first spoken, so it isn't skipped
+    pub started: bool,
+    /// stopped for now, picks up at the same paragraph
+    pub paused: bool,
+    /// words per minute
+    pub speed: u32,
+    sleep_minutes: u64,
+    sleep_at: Option<Instant>,
+}
+
+impl ReadAloud {
+    /// reading from paragraph, nothing is spoken yet
+    pub fn new(config: &TtsConfig, paragraph: usize) -> Self {
+        Self {
+            speaker: Speaker::new(config),
+            paragraph,
+            started: false,
+            paused: false,
+            speed: config.speed,
+            sleep_minutes: 0,
+            sleep_at: None,
+        }
+    }
+
+    /// off -> 15 -> 30 -> 60 minutes -> off
+    pub fn cycle_sleep_timer(&mut self) {
+        let index = SLEEP_TIMERS
+            .iter()
+            .position(|x| *x == self.sleep_minutes)
+            .unwrap_or(0);
+        self.sleep_minutes = SLEEP_TIMERS[(index + 1) % SLEEP_TIMERS.len()];
+        self.sleep_at = match self.sleep_minutes {
+            0 => None,
+            minutes => Some(Instant::now() + Duration::from_secs(minutes * 60)),
+        };
+    }
+
+    /// true once the sleep timer has run out
+    pub fn is_sleep_due(&self) -> bool {
+        match self.sleep_at {
+            Some(at) => Instant::now() >= at,
+            None => false,
+        }
+    }
+
+    /// time until the sleep timer stops reading, None if it's off
+    pub fn sleep_left(&self) -> Option<Duration> {
+        self.sleep_at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+}
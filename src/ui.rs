@@ -0,0 +1,213 @@
+// the terminal calls the screens make, in the style of ncurses, drawn by
+// whichever backend the binary was built with:
+//   ncurses (default)  needs the system ncurses library
+//   crossterm          pure Rust, `--no-default-features --features crossterm`
+// with both built in, LIGHTNOVEL_CLI_BACKEND=crossterm picks crossterm
+#![allow(non_camel_case_types, non_snake_case)]
+
+#[cfg(feature = "crossterm")]
+mod crossterm_backend;
+#[cfg(feature = "ncurses")]
+mod ncurses_backend;
+
+#[cfg(not(any(feature = "ncurses", feature = "crossterm")))]
+compile_error!("enable the ncurses or the crossterm feature");
+
+use std::sync::Mutex;
+
+// same bits as ncurses so its backend can pass them on unchanged, the color
+// pair is in bits 8 to 15
+pub type attr_t = u32;
+pub const A_REVERSE: attr_t = 1 << 18;
+pub const A_DIM: attr_t = 1 << 20;
+pub const A_BOLD: attr_t = 1 << 21;
+pub const A_ITALIC: attr_t = 1 << 31;
+pub const A_COLOR: attr_t = 0xff << 8;
+
+pub fn COLOR_PAIR(pair: i16) -> attr_t {
+    ((pair as attr_t) << 8) & A_COLOR
+}
+
+pub const COLOR_BLACK: i16 = 0;
+pub const COLOR_GREEN: i16 = 2;
+pub const COLOR_YELLOW: i16 = 3;
+pub const COLOR_MAGENTA: i16 = 5;
+pub const COLOR_CYAN: i16 = 6;
+
+// getch() with nothing to read before the timeout
+pub const ERR: i32 = -1;
+pub const KEY_DOWN: i32 = 258;
+pub const KEY_UP: i32 = 259;
+pub const KEY_LEFT: i32 = 260;
+pub const KEY_RIGHT: i32 = 261;
+pub const KEY_HOME: i32 = 262;
+pub const KEY_BACKSPACE: i32 = 263;
+pub const KEY_DC: i32 = 330;
+pub const KEY_NPAGE: i32 = 338;
+pub const KEY_PPAGE: i32 = 339;
+pub const KEY_ENTER: i32 = 343;
+pub const KEY_END: i32 = 360;
+
+pub enum WchResult {
+    // a special key, one of the KEY_ constants
+    KeyCode(i32),
+    // a character, control keys as their ascii code
+    Char(u32),
+}
+
+// what a terminal library has to provide, positions are (row, column) from
+// the top left like in ncurses
+pub trait Backend {
+    fn start(&mut self);
+    fn stop(&mut self);
+    // (rows, columns)
+    fn size(&self) -> (i32, i32);
+    // blank the screen, it's redrawn completely on the next refresh
+    fn clear(&mut self);
+    // show what was drawn since the last refresh
+    fn refresh(&mut self);
+    fn move_to(&mut self, y: i32, x: i32);
+    // text at the cursor in the current attributes, '\n' clears the rest of
+    // the line and moves to the start of the next one
+    fn add_str(&mut self, text: &str);
+    fn clear_to_eol(&mut self);
+    fn set_attr(&mut self, attr: attr_t);
+    // change the attributes of count cells (-1 for the rest of the line)
+    // without touching their text
+    fn change_attr(&mut self, y: i32, x: i32, count: i32, attr: attr_t);
+    // -1 is the default color of the terminal
+    fn init_pair(&mut self, pair: i16, foreground: i16, background: i16);
+    fn show_cursor(&mut self, visible: bool);
+    // milliseconds get_key waits, -1 to wait for a key
+    fn timeout(&mut self, delay: i32);
+    // refreshes first, None if no key came before the timeout
+    fn get_key(&mut self) -> Option<WchResult>;
+}
+
+struct State {
+    backend: Box<dyn Backend + Send>,
+    attr: attr_t,
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+// run f on the backend, nothing happens before initscr or after endwin
+fn with<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&mut State) -> R,
+{
+    STATE.lock().unwrap().as_mut().map(f)
+}
+
+fn backend() -> Box<dyn Backend + Send> {
+    #[cfg(all(feature = "ncurses", feature = "crossterm"))]
+    if std::env::var("LIGHTNOVEL_CLI_BACKEND").as_deref() == Ok("crossterm") {
+        return Box::new(crossterm_backend::Crossterm::new());
+    }
+    #[cfg(feature = "ncurses")]
+    return Box::new(ncurses_backend::Ncurses);
+    #[cfg(not(feature = "ncurses"))]
+    return Box::new(crossterm_backend::Crossterm::new());
+}
+
+// take over the terminal: raw input, keypad keys, colors, no echo
+pub fn initscr() {
+    let mut backend = backend();
+    backend.start();
+    *STATE.lock().unwrap() = Some(State { backend, attr: 0 });
+}
+
+pub fn endwin() {
+    if let Some(mut state) = STATE.lock().unwrap().take() {
+        state.backend.stop();
+    }
+}
+
+pub fn getmaxyx(y: &mut i32, x: &mut i32) {
+    if let Some((rows, columns)) = with(|x| x.backend.size()) {
+        *y = rows;
+        *x = columns;
+    }
+}
+
+// true if the screen isn't rows by columns anymore
+pub fn is_term_resized(rows: i32, columns: i32) -> bool {
+    with(|x| x.backend.size() != (rows, columns)).unwrap_or(false)
+}
+
+pub fn clear() {
+    with(|x| x.backend.clear());
+}
+
+pub fn refresh() {
+    with(|x| x.backend.refresh());
+}
+
+pub fn mv(y: i32, x: i32) {
+    with(|state| state.backend.move_to(y, x));
+}
+
+pub fn addstr(text: &str) {
+    with(|x| x.backend.add_str(text));
+}
+
+pub fn mvaddstr(y: i32, x: i32, text: &str) {
+    with(|state| {
+        state.backend.move_to(y, x);
+        state.backend.add_str(text);
+    });
+}
+
+pub fn clrtoeol() {
+    with(|x| x.backend.clear_to_eol());
+}
+
+pub fn attron(attr: attr_t) {
+    with(|state| {
+        // a new color pair replaces the old one
+        if attr & A_COLOR != 0 {
+            state.attr &= !A_COLOR;
+        }
+        state.attr |= attr;
+        state.backend.set_attr(state.attr);
+    });
+}
+
+pub fn attroff(attr: attr_t) {
+    with(|state| {
+        state.attr &= !attr;
+        state.backend.set_attr(state.attr);
+    });
+}
+
+pub fn mvchgat(y: i32, x: i32, count: i32, attr: attr_t, pair: i16) {
+    with(|state| {
+        let attr = (attr & !A_COLOR) | COLOR_PAIR(pair);
+        state.backend.change_attr(y, x, count, attr)
+    });
+}
+
+pub fn init_pair(pair: i16, foreground: i16, background: i16) {
+    with(|x| x.backend.init_pair(pair, foreground, background));
+}
+
+pub fn curs_set(visible: bool) {
+    with(|x| x.backend.show_cursor(visible));
+}
+
+pub fn timeout(delay: i32) {
+    with(|x| x.backend.timeout(delay));
+}
+
+pub fn get_wch() -> Option<WchResult> {
+    with(|x| x.backend.get_key()).flatten()
+}
+
+// the next key as a character or KEY_ code, ERR on timeout
+pub fn getch() -> i32 {
+    match get_wch() {
+        Some(WchResult::Char(ch)) => ch as i32,
+        Some(WchResult::KeyCode(code)) => code,
+        None => ERR,
+    }
+}
@@ -0,0 +1,368 @@
+use std::io::{stdout, Stdout, Write};
+use std::time::Duration;
+
+use crossterm::{
+    cursor, event,
+    event::{Event, KeyCode, KeyEventKind, KeyModifiers},
+    queue,
+    style::{self, Attribute, Color},
+    terminal,
+};
+use unicode_width::UnicodeWidthChar;
+
+use super::*;
+
+// what ncurses' get_wch returns when the terminal changed size
+const KEY_RESIZE: i32 = 410;
+const KEY_F0: i32 = 264;
+const KEY_IC: i32 = 331;
+const KEY_BTAB: i32 = 353;
+
+#[derive(Clone, PartialEq)]
+struct Cell {
+    // empty for the second column of a wide character
+    text: String,
+    attr: attr_t,
+}
+
+impl Cell {
+    fn blank(attr: attr_t) -> Cell {
+        Cell {
+            text: " ".to_string(),
+            attr,
+        }
+    }
+}
+
+// draws into a buffer of cells like ncurses' stdscr and writes only the
+// cells that changed since the last refresh
+pub struct Crossterm {
+    out: Stdout,
+    rows: i32,
+    columns: i32,
+    cells: Vec<Cell>,
+    shown: Vec<Cell>,
+    y: i32,
+    x: i32,
+    attr: attr_t,
+    pairs: Vec<(i16, i16)>,
+    delay: i32,
+    cursor: bool,
+    // draw every cell on the next refresh, not only the changed ones
+    redraw: bool,
+}
+
+impl Crossterm {
+    pub fn new() -> Crossterm {
+        let mut backend = Crossterm {
+            out: stdout(),
+            rows: 0,
+            columns: 0,
+            cells: Vec::new(),
+            shown: Vec::new(),
+            y: 0,
+            x: 0,
+            attr: 0,
+            pairs: vec![(-1, -1); 256],
+            delay: -1,
+            cursor: true,
+            redraw: true,
+        };
+        backend.fit();
+        backend
+    }
+
+    // resize the buffers to the terminal, blanking them if it changed
+    fn fit(&mut self) {
+        let (rows, columns) = self.size();
+        if (rows, columns) == (self.rows, self.columns) {
+            return;
+        }
+        self.rows = rows;
+        self.columns = columns;
+        self.cells = vec![Cell::blank(0); (rows * columns) as usize];
+        self.shown = self.cells.clone();
+        self.y = self.y.min(rows - 1).max(0);
+        self.x = self.x.min(columns - 1).max(0);
+        self.redraw = true;
+    }
+
+    fn index(&self, y: i32, x: i32) -> Option<usize> {
+        if y < 0 || x < 0 || y >= self.rows || x >= self.columns {
+            None
+        } else {
+            Some((y * self.columns + x) as usize)
+        }
+    }
+
+    fn put(&mut self, ch: char) {
+        let width = ch.width().unwrap_or(0) as i32;
+        if width == 0 {
+            // combining marks go with the character before them
+            if let Some(i) = self.index(self.y, self.x - 1) {
+                self.cells[i].text.push(ch);
+            }
+            return;
+        }
+        if self.x + width > self.columns {
+            self.y += 1;
+            self.x = 0;
+        }
+        let Some(i) = self.index(self.y, self.x) else {
+            return;
+        };
+        // don't leave half of a wide character behind
+        if self.cells[i].text.is_empty() && self.x > 0 {
+            self.cells[i - 1] = Cell::blank(self.cells[i - 1].attr);
+        }
+        self.cells[i] = Cell {
+            text: ch.to_string(),
+            attr: self.attr,
+        };
+        if width == 2 {
+            self.cells[i + 1] = Cell {
+                text: String::new(),
+                attr: self.attr,
+            };
+        }
+        self.x += width;
+        if let Some(next) = self.index(self.y, self.x) {
+            if self.cells[next].text.is_empty() {
+                self.cells[next] = Cell::blank(self.cells[next].attr);
+            }
+        }
+    }
+
+    // -1, the default color of the terminal, is what the reset leaves
+    fn style(&mut self, attr: attr_t) -> std::io::Result<()> {
+        queue!(self.out, style::SetAttribute(Attribute::Reset))?;
+        let (foreground, background) =
+            self.pairs[((attr & A_COLOR) >> 8) as usize];
+        if foreground >= 0 {
+            queue!(
+                self.out,
+                style::SetForegroundColor(Color::AnsiValue(foreground as u8))
+            )?;
+        }
+        if background >= 0 {
+            queue!(
+                self.out,
+                style::SetBackgroundColor(Color::AnsiValue(background as u8))
+            )?;
+        }
+        for (bit, attribute) in [
+            (A_BOLD, Attribute::Bold),
+            (A_DIM, Attribute::Dim),
+            (A_REVERSE, Attribute::Reverse),
+            (A_ITALIC, Attribute::Italic),
+        ] {
+            if attr & bit != 0 {
+                queue!(self.out, style::SetAttribute(attribute))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self) -> std::io::Result<()> {
+        queue!(self.out, cursor::Hide)?;
+        if self.redraw {
+            queue!(
+                self.out,
+                style::SetAttribute(Attribute::Reset),
+                terminal::Clear(terminal::ClearType::All)
+            )?;
+        }
+        // the terminal cursor, None when it has to be moved first
+        let mut at = None;
+        let mut attr = None;
+        for y in 0..self.rows {
+            for x in 0..self.columns {
+                let i = (y * self.columns + x) as usize;
+                let cell = &self.cells[i];
+                if cell.text.is_empty()
+                    || (!self.redraw && *cell == self.shown[i])
+                {
+                    continue;
+                }
+                let (text, cell_attr) = (cell.text.clone(), cell.attr);
+                if at != Some((y, x)) {
+                    queue!(self.out, cursor::MoveTo(x as u16, y as u16))?;
+                }
+                if attr != Some(cell_attr) {
+                    self.style(cell_attr)?;
+                    attr = Some(cell_attr);
+                }
+                queue!(self.out, style::Print(&text))?;
+                let width = text.chars().next().and_then(|x| x.width());
+                at = Some((y, x + width.unwrap_or(1) as i32));
+            }
+        }
+        self.shown.clone_from(&self.cells);
+        self.redraw = false;
+        queue!(
+            self.out,
+            style::SetAttribute(Attribute::Reset),
+            cursor::MoveTo(self.x as u16, self.y as u16)
+        )?;
+        if self.cursor {
+            queue!(self.out, cursor::Show)?;
+        }
+        self.out.flush()
+    }
+
+    fn read_key(&mut self) -> std::io::Result<Option<WchResult>> {
+        loop {
+            if self.delay >= 0
+                && !event::poll(Duration::from_millis(self.delay as u64))?
+            {
+                return Ok(None);
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    if let Some(key) = map_key(key.code, key.modifiers) {
+                        return Ok(Some(key));
+                    }
+                }
+                Event::Resize(..) => {
+                    self.fit();
+                    return Ok(Some(WchResult::KeyCode(KEY_RESIZE)));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Backend for Crossterm {
+    fn start(&mut self) {
+        let _ = terminal::enable_raw_mode();
+        let _ = queue!(self.out, terminal::EnterAlternateScreen);
+        self.redraw = true;
+    }
+
+    fn stop(&mut self) {
+        let _ = queue!(
+            self.out,
+            style::SetAttribute(Attribute::Reset),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = self.out.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+
+    fn size(&self) -> (i32, i32) {
+        terminal::size()
+            .map(|(columns, rows)| (rows as i32, columns as i32))
+            .unwrap_or((24, 80))
+    }
+
+    // only the cells that end up different are written on the next refresh,
+    // the cursor goes home like with ncurses' clear
+    fn clear(&mut self) {
+        self.fit();
+        self.cells.fill(Cell::blank(0));
+        self.y = 0;
+        self.x = 0;
+    }
+
+    fn refresh(&mut self) {
+        self.fit();
+        let _ = self.draw();
+    }
+
+    fn move_to(&mut self, y: i32, x: i32) {
+        self.y = y;
+        self.x = x;
+    }
+
+    fn add_str(&mut self, text: &str) {
+        for ch in text.chars() {
+            match ch {
+                '\n' => {
+                    self.clear_to_eol();
+                    self.y += 1;
+                    self.x = 0;
+                }
+                '\t' => {
+                    for _ in 0..8 - self.x % 8 {
+                        self.put(' ');
+                    }
+                }
+                ch if ch.is_control() => {}
+                ch => self.put(ch),
+            }
+        }
+    }
+
+    fn clear_to_eol(&mut self) {
+        for x in self.x..self.columns {
+            if let Some(i) = self.index(self.y, x) {
+                self.cells[i] = Cell::blank(0);
+            }
+        }
+    }
+
+    fn set_attr(&mut self, attr: attr_t) {
+        self.attr = attr;
+    }
+
+    fn change_attr(&mut self, y: i32, x: i32, count: i32, attr: attr_t) {
+        let end = if count < 0 { self.columns } else { x + count };
+        for x in x..end.min(self.columns) {
+            if let Some(i) = self.index(y, x) {
+                self.cells[i].attr = attr;
+            }
+        }
+    }
+
+    fn init_pair(&mut self, pair: i16, foreground: i16, background: i16) {
+        if let Some(colors) = self.pairs.get_mut(pair as usize) {
+            *colors = (foreground, background);
+        }
+    }
+
+    fn show_cursor(&mut self, visible: bool) {
+        self.cursor = visible;
+    }
+
+    fn timeout(&mut self, delay: i32) {
+        self.delay = delay;
+    }
+
+    fn get_key(&mut self) -> Option<WchResult> {
+        self.refresh();
+        self.read_key().ok().flatten()
+    }
+}
+
+// keys the way ncurses reports them in raw mode
+fn map_key(code: KeyCode, modifiers: KeyModifiers) -> Option<WchResult> {
+    use WchResult::{Char, KeyCode as Key};
+    Some(match code {
+        KeyCode::Char(ch)
+            if modifiers.contains(KeyModifiers::CONTROL)
+                && ch.is_ascii_alphabetic() =>
+        {
+            Char(ch.to_ascii_lowercase() as u32 & 0x1f)
+        }
+        KeyCode::Char(ch) => Char(ch as u32),
+        KeyCode::Enter => Char(10),
+        KeyCode::Tab => Char(9),
+        KeyCode::Esc => Char(27),
+        KeyCode::Backspace => Char(127),
+        KeyCode::BackTab => Key(KEY_BTAB),
+        KeyCode::Up => Key(KEY_UP),
+        KeyCode::Down => Key(KEY_DOWN),
+        KeyCode::Left => Key(KEY_LEFT),
+        KeyCode::Right => Key(KEY_RIGHT),
+        KeyCode::Home => Key(KEY_HOME),
+        KeyCode::End => Key(KEY_END),
+        KeyCode::PageUp => Key(KEY_PPAGE),
+        KeyCode::PageDown => Key(KEY_NPAGE),
+        KeyCode::Delete => Key(KEY_DC),
+        KeyCode::Insert => Key(KEY_IC),
+        KeyCode::F(n) => Key(KEY_F0 + n as i32),
+        _ => return None,
+    })
+}
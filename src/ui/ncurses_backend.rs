@@ -0,0 +1,81 @@
+use ncurses as nc;
+
+use super::{attr_t, Backend, WchResult, A_COLOR};
+
+pub struct Ncurses;
+
+impl Backend for Ncurses {
+    fn start(&mut self) {
+        nc::setlocale(nc::LcCategory::all, "");
+        nc::initscr();
+        nc::keypad(nc::stdscr(), true);
+        nc::use_default_colors();
+        nc::start_color();
+        nc::noecho();
+        nc::raw();
+        // esc cancels prompts, don't wait a second for an escape sequence
+        nc::set_escdelay(25);
+    }
+
+    fn stop(&mut self) {
+        nc::endwin();
+    }
+
+    fn size(&self) -> (i32, i32) {
+        let (mut rows, mut columns) = (0, 0);
+        nc::getmaxyx(nc::stdscr(), &mut rows, &mut columns);
+        (rows, columns)
+    }
+
+    fn clear(&mut self) {
+        nc::clear();
+    }
+
+    fn refresh(&mut self) {
+        nc::refresh();
+    }
+
+    fn move_to(&mut self, y: i32, x: i32) {
+        nc::mv(y, x);
+    }
+
+    fn add_str(&mut self, text: &str) {
+        nc::addstr(text);
+    }
+
+    fn clear_to_eol(&mut self) {
+        nc::clrtoeol();
+    }
+
+    fn set_attr(&mut self, attr: attr_t) {
+        nc::attrset(attr as nc::attr_t);
+    }
+
+    fn change_attr(&mut self, y: i32, x: i32, count: i32, attr: attr_t) {
+        let pair = ((attr & A_COLOR) >> 8) as i16;
+        nc::mvchgat(y, x, count, (attr & !A_COLOR) as nc::attr_t, pair);
+    }
+
+    fn init_pair(&mut self, pair: i16, foreground: i16, background: i16) {
+        nc::init_pair(pair, foreground, background);
+    }
+
+    fn show_cursor(&mut self, visible: bool) {
+        nc::curs_set(if visible {
+            nc::CURSOR_VISIBILITY::CURSOR_VISIBLE
+        } else {
+            nc::CURSOR_VISIBILITY::CURSOR_INVISIBLE
+        });
+    }
+
+    fn timeout(&mut self, delay: i32) {
+        nc::timeout(delay);
+    }
+
+    fn get_key(&mut self) -> Option<WchResult> {
+        match nc::get_wch()? {
+            nc::WchResult::KeyCode(code) => Some(WchResult::KeyCode(code)),
+            nc::WchResult::Char(ch) => Some(WchResult::Char(ch)),
+        }
+    }
+}